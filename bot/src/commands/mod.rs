@@ -12,13 +12,18 @@ pub fn all_commands() -> Vec<poise::Command<Data, Error>> {
         music::volume(),
         music::queue(),
         music::skip(),
+        music::previous(),
         music::pause(),
         music::resume(),
         music::stop(),
         music::seek(),
         music::clear(),
         music::remove(),
-        music::swap()
+        music::swap(),
+        music::move_track(),
+        music::filter(),
+        music::search(),
+        music::lyrics()
     ];
 
     poise::framework::set_qualified_names(&mut all_commands);
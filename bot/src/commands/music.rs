@@ -4,11 +4,15 @@ use std::ops::Deref;
 use futures::future;
 use futures::stream::StreamExt;
 use lavalink_rs::prelude::*;
+use lavalink_rs::error::LavalinkError;
+use lavalink_rs::model::player::{BassBoost, FilterPreset};
+use lavalink_rs::model::search::{LavaSearchEngines, SearchType};
+use lavalink_rs::model::sponsorblock::SegmentCategory;
 
 use crate::abort_with;
 use crate::checker::*;
 use crate::utils::{
-    prelude::{Context, Error},
+    prelude::{Context, Error, TrackUserData},
     ctx::PoiseContextExt
 };
 
@@ -51,7 +55,7 @@ async fn _join(
 
         return match handler {
             Ok((connection_info, _)) => {
-                lava_client
+                let player = lava_client
                     .create_player_context_with_data::<(ChannelId, std::sync::Arc<Http>)>(
                         guild_id,
                         connection_info,
@@ -62,6 +66,14 @@ async fn _join(
                     )
                     .await?;
 
+                // Без плагина SponsorBlock просто ничего не пропускаем
+                let _ = player.set_sponsorblock_categories(&[
+                    SegmentCategory::Sponsor,
+                    SegmentCategory::SelfPromo,
+                    SegmentCategory::Intro,
+                    SegmentCategory::Outro,
+                ]).await;
+
                 let embed = CreateEmbed::default()
                     .title("Подключен!")
                     .description(format!("Бот присоединился к каналу {}.", connect_to.mention()))
@@ -138,12 +150,16 @@ pub async fn play(
         }
     };
 
+    let user_data = TrackUserData { requester_id: ctx.author().id.get() };
+
     for i in &mut tracks {
-        i.track.user_data = Some(serde_json::json!({"requester_id": ctx.author().id.get()}));
+        i.track.set_user_data(&user_data)?;
+        i.fade_in = Some(Duration::from_secs(2));
+        i.fade_out = Some(Duration::from_secs(3));
     }
 
     let queue = player.get_queue();
-    queue.append(tracks.clone().into())?;
+    queue.append(tracks.clone().into()).await?;
 
     let mut embed = CreateEmbed::new();
 
@@ -256,8 +272,12 @@ pub async fn now_playing(ctx: Context<'_>) -> Result<(), Error> {
         let time_m = player_data.state.position / 1000 / 60;
         let time = format!("{:02}:{:02}", time_m, time_s);
 
+        if let Ok(user_data) = track.user_data_as::<TrackUserData>() {
+            now_embed = now_embed
+                .field("Добавил", format!("<@{}>", user_data.requester_id), true);
+        }
+
         now_embed = now_embed
-            .field("Добавил", format!("<@{}>", track.user_data.clone().unwrap()["requester_id"]), true)
             .field("Автор", &track.info.author, true)
             .field("Время", time, true);
 
@@ -296,6 +316,8 @@ pub async fn queue(ctx: Context<'_>) -> Result<(), Error> {
 
     let queue = player.get_queue();
 
+    let history = queue.history().await?;
+
     let max = queue.get_count().await?.min(9);
     let queue_message = queue
         .enumerate()
@@ -322,10 +344,28 @@ pub async fn queue(ctx: Context<'_>) -> Result<(), Error> {
         .await
         .join("\n");
 
-    let queue_embed = CreateEmbed::default()
+    let history_message = history
+        .iter()
+        .rev()
+        .take(5)
+        .map(|x| {
+            if let Some(uri) = &x.track.info.uri {
+                format!("[{} - {}](<{}>)", x.track.info.author, x.track.info.title, uri)
+            } else {
+                format!("{} - {}", x.track.info.author, x.track.info.title)
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n");
+
+    let mut queue_embed = CreateEmbed::default()
         .title("Очередь треков")
         .description(queue_message);
 
+    if !history_message.is_empty() {
+        queue_embed = queue_embed.field("Недавно играли", history_message, false);
+    }
+
     let builder = CreateReply::default()
         .ephemeral(true)
         .embed(queue_embed);
@@ -344,7 +384,7 @@ pub async fn skip(ctx: Context<'_>) -> Result<(), Error> {
     let now_playing = player.get_player().await?.track;
 
     if let Some(np) = now_playing {
-        player.skip()?;
+        player.skip_with_fade(Duration::from_secs(1)).await?;
         ctx.say_success(format!("Пропущен {}", np.info.title)).await?;
     } else {
         ctx.say_error("Нечего пропускать").await?;
@@ -353,6 +393,22 @@ pub async fn skip(ctx: Context<'_>) -> Result<(), Error> {
     Ok(())
 }
 
+/// Возвращается к предыдущему треку.
+#[poise::command(slash_command, prefix_command, check = "voice_check")]
+pub async fn previous(ctx: Context<'_>) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let lava_client = ctx.data().lavalink.clone();
+    let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
+
+    if let Some(previous) = player.previous().await? {
+        ctx.say_success(format!("Возвращён {}", previous.track.info.title)).await?;
+    } else {
+        ctx.say_error("История пуста").await?;
+    }
+
+    Ok(())
+}
+
 /// Pause the current song.
 #[poise::command(slash_command, prefix_command, check = "voice_check")]
 pub async fn pause(ctx: Context<'_>) -> Result<(), Error> {
@@ -388,7 +444,7 @@ pub async fn stop(ctx: Context<'_>) -> Result<(), Error> {
     let now_playing = player.get_player().await?.track;
 
     if let Some(np) = now_playing {
-        player.stop_now().await?;
+        player.stop_with_fade(Duration::from_secs(1)).await?;
         ctx.say(format!("Остановлен {}", np.info.title)).await?;
     } else {
         ctx.say_error("Нечего останавливать").await?;
@@ -429,8 +485,13 @@ pub async fn remove(
     let lava_client = ctx.data().lavalink.clone();
     let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
 
-    player.get_queue().remove(index)?;
-    ctx.say_success("Трек удалён").await?;
+    match player.get_queue().remove(index).await {
+        Ok(_) => ctx.say_success("Трек удалён").await?,
+        Err(LavalinkError::IndexOutOfBounds { len, .. }) => {
+            ctx.say_error(format!("В очереди всего {} треков", len)).await?
+        }
+        Err(why) => return Err(why.into()),
+    };
 
     Ok(())
 }
@@ -442,7 +503,7 @@ pub async fn clear(ctx: Context<'_>) -> Result<(), Error> {
     let lava_client = ctx.data().lavalink.clone();
     let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
 
-    player.get_queue().clear()?;
+    player.get_queue().clear().await?;
     ctx.say_success("Очередь очищена").await?;
 
     Ok(())
@@ -459,26 +520,193 @@ pub async fn swap(
     let lava_client = ctx.data().lavalink.clone();
     let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
 
-    let queue = player.get_queue();
-    let queue_len = queue.get_count().await?;
-
-    if index1 > queue_len || index2 > queue_len {
-        ctx.say_error(format!("Максимально разрешённаый номер: {}", queue_len))
-            .await?;
+    if index1 == 0 || index2 == 0 {
+        ctx.say_error("Номера в очереди начинаются с 1").await?;
         return Ok(());
     } else if index1 == index2 {
         ctx.say_error("Нельзя поменять между одинаковыми номерами").await?;
         return Ok(());
     }
 
-    let track1 = queue.get_track(index1 - 1).await?.unwrap();
-    let track2 = queue.get_track(index1 - 2).await?.unwrap();
+    match player.get_queue().swap_indices(index1 - 1, index2 - 1).await {
+        Ok(()) => ctx.say_success("Места изменены").await?,
+        Err(LavalinkError::IndexOutOfBounds { len, .. }) => {
+            ctx.say_error(format!("Максимально разрешённаый номер: {}", len)).await?
+        }
+        Err(why) => return Err(why.into()),
+    };
+
+    Ok(())
+}
 
-    queue.swap(index1 - 1, track2)?;
-    queue.swap(index2 - 1, track1)?;
+/// Move a song to another position in the queue.
+#[poise::command(slash_command, prefix_command, rename = "move", check = "voice_check")]
+pub async fn move_track(
+    ctx: Context<'_>,
+    #[description = "Queue item index to move"] from: usize,
+    #[description = "The queue position to move it to"] to: usize,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let lava_client = ctx.data().lavalink.clone();
+    let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
 
-    ctx.say_success("Места изменены").await?;
+    if from == 0 || to == 0 {
+        ctx.say_error("Номера в очереди начинаются с 1").await?;
+        return Ok(());
+    }
+
+    match player.get_queue().move_track(from - 1, to - 1).await {
+        Ok(()) => ctx.say_success("Трек перемещён").await?,
+        Err(LavalinkError::IndexOutOfBounds { len, .. }) => {
+            ctx.say_error(format!("Максимально разрешённаый номер: {}", len)).await?
+        }
+        Err(why) => return Err(why.into()),
+    };
 
     Ok(())
 }
 
+#[derive(Debug, poise::ChoiceParameter)]
+pub enum FilterChoice {
+    #[name = "Усиление басов"]
+    BassBoost,
+    #[name = "Сильное усиление басов"]
+    HeavyBassBoost,
+    #[name = "Nightcore"]
+    Nightcore,
+    #[name = "Vaporwave"]
+    Vaporwave,
+    #[name = "8D"]
+    EightD,
+    #[name = "Мягкий звук"]
+    Soft,
+    #[name = "Караоке"]
+    Karaoke,
+    #[name = "Выключить все"]
+    Reset,
+}
+
+/// Применяет фильтр к проигрываемой музыке.
+#[poise::command(slash_command, prefix_command, check = "voice_check")]
+pub async fn filter(
+    ctx: Context<'_>,
+    #[description = "Фильтр"] filter: FilterChoice,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let lava_client = ctx.data().lavalink.clone();
+    let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
+
+    let preset = match filter {
+        FilterChoice::BassBoost => Some(FilterPreset::BassBoost(BassBoost::Medium)),
+        FilterChoice::HeavyBassBoost => Some(FilterPreset::BassBoost(BassBoost::Extreme)),
+        FilterChoice::Nightcore => Some(FilterPreset::Nightcore),
+        FilterChoice::Vaporwave => Some(FilterPreset::Vaporwave),
+        FilterChoice::EightD => Some(FilterPreset::EightD),
+        FilterChoice::Soft => Some(FilterPreset::Soft),
+        FilterChoice::Karaoke => Some(FilterPreset::Karaoke),
+        FilterChoice::Reset => None,
+    };
+
+    match preset {
+        Some(preset) => {
+            player.update_filters(move |filters| filters.preset(preset)).await?;
+            ctx.say_success("Фильтр применён").await?;
+        }
+        None => {
+            player.set_filters(Default::default()).await?;
+            ctx.say_success("Фильтры выключены").await?;
+        }
+    }
+
+    Ok(())
+}
+
+/// Ищет треки, альбомы и исполнителей в Spotify.
+#[poise::command(slash_command, prefix_command)]
+pub async fn search(
+    ctx: Context<'_>,
+    #[description = "Запрос"]
+    #[rest]
+    term: String,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let lava_client = ctx.data().lavalink.clone();
+
+    let query = LavaSearchEngines::Spotify.to_query(&term);
+    let result = lava_client
+        .load_search(guild_id, &query, &[SearchType::Track, SearchType::Album, SearchType::Artist, SearchType::Text])
+        .await?;
+
+    if result.tracks.is_empty() && result.albums.is_empty() && result.artists.is_empty() {
+        match result.texts.first() {
+            Some(text) => ctx.say_error(format!("Ничего не найдено. Возможно, вы имели в виду: {}", text.text)).await?,
+            None => ctx.say_error("Ничего не найдено").await?,
+        };
+
+        return Ok(());
+    }
+
+    let mut embed = CreateEmbed::default()
+        .title("Поиск")
+        .color(Color::DARK_GREEN);
+
+    if !result.tracks.is_empty() {
+        let tracks = result.tracks.iter().take(5)
+            .map(|x| format!("{} - {}", x.info.author, x.info.title))
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field("Треки", tracks, false);
+    }
+
+    if !result.albums.is_empty() {
+        let albums = result.albums.iter().take(5)
+            .map(|x| x.info.name.clone())
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field("Альбомы", albums, false);
+    }
+
+    if !result.artists.is_empty() {
+        let artists = result.artists.iter().take(5)
+            .map(|x| x.info.name.clone())
+            .collect::<Vec<_>>()
+            .join("\n");
+        embed = embed.field("Исполнители", artists, false);
+    }
+
+    let builder = CreateReply::default().ephemeral(true).embed(embed);
+    let _ = ctx.send(builder).await;
+
+    Ok(())
+}
+
+/// Показывает текст играющего трека.
+#[poise::command(slash_command, prefix_command, check = "voice_check")]
+pub async fn lyrics(
+    ctx: Context<'_>,
+) -> Result<(), Error> {
+    let guild_id = ctx.guild_id().unwrap();
+    let lava_client = ctx.data().lavalink.clone();
+    let Some(player) = lava_client.get_player_context(guild_id) else { todo!() };
+
+    let Some(lyrics) = player.get_lyrics().await? else {
+        ctx.say_error("Текст не найден").await?;
+        return Ok(());
+    };
+
+    let mut text = lyrics.to_text();
+    if text.chars().count() > 4000 {
+        text = text.chars().take(4000).collect::<String>() + "...";
+    }
+
+    let embed = CreateEmbed::default()
+        .title("Текст")
+        .description(text)
+        .footer(serenity::CreateEmbedFooter::new(format!("Источник: {}", lyrics.provider)))
+        .color(Color::DARK_GREEN);
+
+    let builder = CreateReply::default().embed(embed);
+    let _ = ctx.send(builder).await;
+
+    Ok(())
+}
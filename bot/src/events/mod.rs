@@ -8,6 +8,8 @@ pub fn lava_events() -> Events {
         raw: Some(music::raw_event),
         ready: Some(music::ready_event),
         track_start: Some(music::track_start),
+        queue_ended: Some(music::queue_ended),
+        segment_skipped: Some(music::segment_skipped),
         ..Default::default()
     };
 }
@@ -2,6 +2,8 @@ use tracing::{info};
 use lavalink_rs::{hook, model::events, prelude::*};
 use poise::serenity_prelude::{model::id::ChannelId, Http, CreateEmbed, CreateMessage};
 
+use crate::utils::prelude::TrackUserData;
+
 #[hook]
 pub async fn raw_event(_: LavalinkClient, session_id: String, event: &serde_json::Value) {
     if event["op"].as_str() == Some("event") || event["op"].as_str() == Some("playerUpdate") {
@@ -11,7 +13,9 @@ pub async fn raw_event(_: LavalinkClient, session_id: String, event: &serde_json
 
 #[hook]
 pub async fn ready_event(client: LavalinkClient, session_id: String, event: &events::Ready) {
-    client.delete_all_player_contexts().await.unwrap();
+    if !event.resumed {
+        client.delete_all_player_contexts().await.unwrap();
+    }
     info!("{:?} -> {:?}", session_id, event);
 }
 
@@ -30,12 +34,27 @@ pub async fn track_start(client: LavalinkClient, _session_id: String, event: &ev
     let time = format!("{:02}:{:02}", time_m, time_s);
 
     let mut embed = CreateEmbed::default()
-        .title("Сейчас играет".to_string())
-        .field("Добавил", format!("<@{}>", track.user_data.clone().unwrap()["requester_id"]), true)
+        .title("Сейчас играет".to_string());
+
+    if let Ok(user_data) = track.user_data_as::<TrackUserData>() {
+        embed = embed
+            .field("Добавил", format!("<@{}>", user_data.requester_id), true);
+    }
+
+    embed = embed
         .field("Автор", &track.info.author, true)
         .field("Длительность", time, true);
 
-    if let Some(artwork) = &track.info.artwork_url {
+    let lavasrc = track.lavasrc_info().unwrap_or_default();
+
+    if let Some(album) = &lavasrc.album_name {
+        match &lavasrc.album_url {
+            Some(url) => embed = embed.field("Альбом", format!("[{}](<{}>)", album, url), true),
+            None => embed = embed.field("Альбом", album, true),
+        }
+    }
+
+    if let Some(artwork) = track.info.artwork_url.as_ref().or(lavasrc.artist_artwork_url.as_ref()) {
         embed = embed
             .thumbnail(artwork);
     }
@@ -53,3 +72,37 @@ pub async fn track_start(client: LavalinkClient, _session_id: String, event: &ev
     let builder = CreateMessage::new().embed(embed);
     let _ = channel_id.send_message(http, builder).await;
 }
+
+#[hook]
+pub async fn queue_ended(client: LavalinkClient, _session_id: String, event: &events::QueueEnded) {
+    let Some(player_context) = client.get_player_context(event.guild_id) else { return };
+    let data = player_context
+        .data::<(ChannelId, std::sync::Arc<Http>)>()
+        .unwrap();
+    let (channel_id, http) = (&data.0, &data.1);
+
+    let embed = CreateEmbed::default()
+        .title("Очередь закончилась".to_string())
+        .description(format!("Последним играл `{}`", event.track.info.title));
+
+    let builder = CreateMessage::new().embed(embed);
+    let _ = channel_id.send_message(http, builder).await;
+}
+
+#[hook]
+pub async fn segment_skipped(client: LavalinkClient, _session_id: String, event: &events::SegmentSkipped) {
+    let Some(player_context) = client.get_player_context(event.guild_id) else { return };
+    let data = player_context
+        .data::<(ChannelId, std::sync::Arc<Http>)>()
+        .unwrap();
+    let (channel_id, http) = (&data.0, &data.1);
+
+    let time_s = (event.segment.end - event.segment.start) / 1000;
+
+    let embed = CreateEmbed::default()
+        .title("Фрагмент пропущен".to_string())
+        .description(format!("Пропущено {} сек. ({:?})", time_s, event.segment.category));
+
+    let builder = CreateMessage::new().embed(embed);
+    let _ = channel_id.send_message(http, builder).await;
+}
@@ -43,12 +43,20 @@ async fn main() -> Result<(), Error> {
                     password: std::env::var("LAVALINK_SERVER_PASSWORD").expect("password not found"),
                     user_id: ctx.cache.current_user().id.into(),
                     session_id: None,
+                    reconnect_policy: None,
+                    request_policy: None,
+                    regions: Vec::new(),
                 };
 
-                let client = LavalinkClient::new(
+                let client = LavalinkClient::new_with_options(
                     events::lava_events(),
                     vec![node_local],
                     NodeDistributionStrategy::round_robin(),
+                    ClientOptions {
+                        resume_timeout: Some(std::time::Duration::from_secs(60)),
+                        ..Default::default()
+                    },
+                    std::sync::Arc::new(()),
                 ).await;
 
                 Ok(Data { lavalink: client })
@@ -1,10 +1,17 @@
 use lavalink_rs::client::LavalinkClient;
+use serde::{Deserialize, Serialize};
 
 #[derive(Debug, Clone)]
 pub struct Data {
     pub lavalink: LavalinkClient,
 }
 
+/// Данные, которые прикрепляются к трекам в очереди
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct TrackUserData {
+    pub requester_id: u64,
+}
+
 
 pub type Error = Box<dyn std::error::Error + Send + Sync>;
 pub type Context<'a> = poise::Context<'a, Data, Error>;
\ No newline at end of file
@@ -4,26 +4,53 @@ use crate::node;
 use crate::player_context::*;
 
 use std::collections::VecDeque;
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
 use ::http::header::HeaderMap;
 use arc_swap::{ArcSwap, ArcSwapOption};
-use dashmap::DashMap;
+use dashmap::{mapref::entry::Entry, DashMap};
+use futures::stream::BoxStream;
 use tokio::sync::mpsc::{UnboundedReceiver, UnboundedSender};
-use tokio::sync::Mutex;
+use tokio::sync::{broadcast, Mutex};
+
+/// How many events a subscriber can fall behind by before it starts missing them.
+const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// The voice server of a guild, received from Discord.
+struct VoiceServer {
+    token: String,
+    endpoint: String,
+    /// The voice session of the bot, if it's known already.
+    session_id: Option<String>,
+}
+
+impl From<&player::ConnectionInfo> for VoiceServer {
+    fn from(connection_info: &player::ConnectionInfo) -> Self {
+        VoiceServer {
+            token: connection_info.token.clone(),
+            endpoint: connection_info.endpoint.clone(),
+            session_id: Some(connection_info.session_id.clone()),
+        }
+    }
+}
 
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "python", pyo3::pyclass)]
 /// The main client, where everything gets done, from events to requests to management.
 pub struct LavalinkClient {
-    pub nodes: Vec<Arc<node::Node>>,
+    nodes: Arc<ArcSwap<Vec<Arc<node::Node>>>>,
     pub players: Arc<DashMap<GuildId, (ArcSwapOption<PlayerContext>, Arc<node::Node>)>>,
     pub events: events::Events,
     tx: UnboundedSender<client::ClientMessage>,
     user_id: UserId,
     user_data: Arc<dyn std::any::Any + Send + Sync>,
     strategy: client::NodeDistributionStrategy,
+    pub(crate) options: client::ClientOptions,
+    next_node_id: Arc<AtomicUsize>,
+    pub(crate) event_tx: broadcast::Sender<(usize, events::LavalinkEvent)>,
+    /// The last known voice server endpoint of every guild.
+    voice_endpoints: Arc<DashMap<GuildId, String>>,
 }
 
 impl LavalinkClient {
@@ -55,139 +82,311 @@ impl LavalinkClient {
         nodes: Vec<node::NodeBuilder>,
         strategy: client::NodeDistributionStrategy,
         user_data: Arc<Data>,
+    ) -> LavalinkClient {
+        Self::new_with_options(
+            events,
+            nodes,
+            strategy,
+            client::ClientOptions::default(),
+            user_data,
+        )
+        .await
+    }
+
+    /// Create a new Lavalink Client with custom options and user data.
+    /// It also establish the connection(s) and start listening for events.
+    ///
+    /// # Parameters
+    ///
+    /// - `events`: The lavalink event handler.
+    /// - `nodes`: List of nodes to connect to.
+    /// - `options`: Client-wide settings, like session resuming.
+    /// - `user_data`: Set the data that will be accessible from anywhere with the client.
+    pub async fn new_with_options<Data: std::any::Any + Send + Sync>(
+        events: events::Events,
+        nodes: Vec<node::NodeBuilder>,
+        strategy: client::NodeDistributionStrategy,
+        options: client::ClientOptions,
+        user_data: Arc<Data>,
     ) -> LavalinkClient {
         if nodes.is_empty() {
             panic!("At least one node must be provided.");
         }
 
-        let mut built_nodes = Vec::new();
-
-        for (idx, i) in nodes.into_iter().enumerate() {
-            let mut headers = HeaderMap::new();
-            headers.insert("Authorization", i.password.parse().unwrap());
-            headers.insert("User-Id", i.user_id.0.to_string().parse().unwrap());
-            headers.insert("Connection", "keep-alive".parse().unwrap());
-
-            if let Some(session_id) = &i.session_id {
-                headers.insert("Session-Id", session_id.parse().unwrap());
-            }
-
-            headers.insert(
-                "Client-Name",
-                format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
-                    .to_string()
-                    .parse()
-                    .unwrap(),
-            );
-
-            #[cfg(feature = "native-tls")]
-            let https_connector = hyper_tls::HttpsConnector::new();
-
-            let request_client =
-                hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
-                    .pool_idle_timeout(std::time::Duration::from_secs(60))
-                    .pool_timer(hyper_util::rt::TokioTimer::new())
-                    .build(https_connector);
-
-            let node = if i.is_ssl {
-                let http = crate::http::Http {
-                    authority: i.hostname.clone(),
-                    rest_address: format!("https://{}/v4", i.hostname),
-                    rest_address_versionless: format!("https://{}", i.hostname),
-                    headers,
-                    request_client: request_client.into(),
-                };
-
-                node::Node {
-                    id: idx,
-                    websocket_address: format!("wss://{}/v4/websocket", i.hostname),
-                    http,
-                    events: i.events,
-                    password: Secret(i.password.into()),
-                    user_id: i.user_id,
-                    is_running: AtomicBool::new(false),
-                    session_id: ArcSwap::new(if let Some(id) = i.session_id {
-                        id.into()
-                    } else {
-                        idx.to_string().into()
-                    }),
-                    cpu: ArcSwap::new(Default::default()),
-                    memory: ArcSwap::new(Default::default()),
-                }
-            } else {
-                let http = crate::http::Http {
-                    authority: i.hostname.clone(),
-                    rest_address: format!("http://{}/v4", i.hostname),
-                    rest_address_versionless: format!("http://{}", i.hostname),
-                    headers,
-                    request_client: request_client.into(),
-                };
-
-                node::Node {
-                    id: idx,
-                    websocket_address: format!("ws://{}/v4/websocket", i.hostname),
-                    http,
-                    events: i.events,
-                    password: Secret(i.password.into()),
-                    user_id: i.user_id,
-                    is_running: AtomicBool::new(false),
-                    session_id: ArcSwap::new(if let Some(id) = i.session_id {
-                        id.into()
-                    } else {
-                        idx.to_string().into()
-                    }),
-                    cpu: ArcSwap::new(Default::default()),
-                    memory: ArcSwap::new(Default::default()),
-                }
-            };
-
-            let node_arc = Arc::new(node);
-
-            built_nodes.push(node_arc);
-        }
+        let built_nodes = nodes
+            .into_iter()
+            .enumerate()
+            .map(|(idx, i)| Arc::new(Self::build_node(idx, i, &options)))
+            .collect::<Vec<_>>();
 
         let (tx, rx) = tokio::sync::mpsc::unbounded_channel();
 
         let client = LavalinkClient {
             user_id: built_nodes[0].user_id,
-            nodes: built_nodes,
+            next_node_id: Arc::new(AtomicUsize::new(built_nodes.len())),
+            nodes: Arc::new(ArcSwap::from_pointee(built_nodes)),
             players: Arc::new(DashMap::new()),
             events,
             tx,
             user_data,
             strategy,
+            options,
+            event_tx: broadcast::channel(EVENT_CHANNEL_CAPACITY).0,
+            voice_endpoints: Arc::new(DashMap::new()),
         };
 
-        for node in &*client.nodes {
+        for node in &**client.nodes.load() {
             if let Err(why) = node.connect(client.clone()).await {
                 error!("Failed to connect to the lavalink websocket: {}", why);
+                tokio::spawn(client.clone().handle_node_lost(node.id));
             }
         }
 
         tokio::spawn(LavalinkClient::handle_connection_info(client.clone(), rx));
 
-        let lavalink_client = client.clone();
+        client
+    }
 
-        tokio::spawn(async move {
-            loop {
-                tokio::time::sleep(std::time::Duration::from_secs(15)).await;
+    fn build_node(id: usize, i: node::NodeBuilder, options: &client::ClientOptions) -> node::Node {
+        let mut headers = HeaderMap::new();
+        headers.insert("Authorization", i.password.parse().unwrap());
+        headers.insert("User-Id", i.user_id.0.to_string().parse().unwrap());
+        headers.insert("Connection", "keep-alive".parse().unwrap());
 
-                for node in &*lavalink_client.nodes {
-                    if !node.is_running.load(Ordering::SeqCst) {
-                        if let Err(why) = node.connect(lavalink_client.clone()).await {
-                            error!("Failed to connect to the lavalink websocket: {}", why);
-                        }
+        if let Some(session_id) = &i.session_id {
+            headers.insert("Session-Id", session_id.parse().unwrap());
+        }
+
+        headers.insert(
+            "Client-Name",
+            format!("{}/{}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))
+                .to_string()
+                .parse()
+                .unwrap(),
+        );
+
+        #[cfg(feature = "native-tls")]
+        let https_connector = hyper_tls::HttpsConnector::new();
+
+        let request_client =
+            hyper_util::client::legacy::Client::builder(hyper_util::rt::TokioExecutor::new())
+                .pool_idle_timeout(std::time::Duration::from_secs(60))
+                .pool_timer(hyper_util::rt::TokioTimer::new())
+                .build(https_connector);
+
+        let (http_scheme, websocket_scheme) = if i.is_ssl {
+            ("https", "wss")
+        } else {
+            ("http", "ws")
+        };
+
+        let http = crate::http::Http {
+            authority: i.hostname.clone(),
+            rest_address: format!("{}://{}/v4", http_scheme, i.hostname),
+            rest_address_versionless: format!("{}://{}", http_scheme, i.hostname),
+            headers,
+            request_client: request_client.into(),
+            policy: i
+                .request_policy
+                .unwrap_or_else(|| options.request_policy.clone()),
+            cancel: Default::default(),
+        };
+
+        node::Node {
+            id,
+            websocket_address: format!("{}://{}/v4/websocket", websocket_scheme, i.hostname),
+            http,
+            events: i.events,
+            password: Secret(i.password.into()),
+            user_id: i.user_id,
+            is_running: AtomicBool::new(false),
+            is_reconnecting: AtomicBool::new(false),
+            is_removed: AtomicBool::new(false),
+            connection: ArcSwapOption::new(None),
+            reconnect_policy: i
+                .reconnect_policy
+                .unwrap_or_else(|| options.reconnect_policy.clone()),
+            regions: i.regions,
+            session_id: ArcSwap::new(if let Some(id) = i.session_id {
+                id.into()
+            } else {
+                id.to_string().into()
+            }),
+            cpu: ArcSwap::new(Default::default()),
+            memory: ArcSwap::new(Default::default()),
+            stats: ArcSwapOption::new(None),
+        }
+    }
+
+    /// Subscribe to the events of every node.
+    ///
+    /// Unlike the hooks in `Events`, any number of subscribers can listen at once, each one
+    /// receiving every event dispatched after it subscribed. The stream yields the ID of the node
+    /// the event came from along with the event, and can be narrowed down with
+    /// `EventStream::guild` and `EventStream::node`.
+    ///
+    /// A subscriber that falls too far behind misses the oldest events it did not receive yet.
+    pub fn subscribe(&self) -> EventStream {
+        let stream = futures::stream::unfold(self.event_tx.subscribe(), |mut rx| async move {
+            loop {
+                match rx.recv().await {
+                    Ok(event) => return Some((event, rx)),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("Event subscriber lagged behind, skipped {} events", skipped);
                     }
+                    Err(broadcast::error::RecvError::Closed) => return None,
                 }
             }
         });
 
-        client
+        EventStream {
+            stream: Box::pin(stream),
+            guild_id: None,
+            node_id: None,
+        }
     }
 
-    // Get a node based on the vector index when insrted into the client initially.
+    /// Get the current list of nodes.
+    ///
+    /// This is a snapshot, so nodes added or removed afterwards are not reflected in it.
+    pub fn nodes(&self) -> Arc<Vec<Arc<node::Node>>> {
+        self.nodes.load_full()
+    }
+
+    /// Get a node based on its position in the current list of nodes.
+    ///
+    /// NOTE: The index is not the ID of the node. Positions shift when a node is removed, so
+    /// use `get_node_by_id` to refer to the same node over time.
     pub fn get_node_by_index(&self, idx: usize) -> Option<Arc<node::Node>> {
-        self.nodes.get(idx).cloned()
+        self.nodes.load().get(idx).cloned()
+    }
+
+    /// Get a node by its ID.
+    ///
+    /// IDs are assigned in insertion order and never reused, so they stay the same when other
+    /// nodes are added or removed.
+    pub fn get_node_by_id(&self, id: usize) -> Option<Arc<node::Node>> {
+        self.nodes.load().iter().find(|x| x.id == id).cloned()
+    }
+
+    /// Add a node to the client and connect to it.
+    ///
+    /// If the connection fails, the node is reconnected following its reconnect policy.
+    pub async fn add_node(&self, node: node::NodeBuilder) -> Arc<node::Node> {
+        let id = self.next_node_id.fetch_add(1, Ordering::SeqCst);
+        let node = Arc::new(Self::build_node(id, node, &self.options));
+
+        self.nodes.rcu(|nodes| {
+            let mut nodes = (**nodes).clone();
+            nodes.push(node.clone());
+            nodes
+        });
+
+        info!("Added node {} ({})", node.id, node.websocket_address);
+
+        if let Err(why) = node.connect(self.clone()).await {
+            error!("Failed to connect to the lavalink websocket: {}", why);
+            tokio::spawn(self.clone().handle_node_lost(node.id));
+        }
+
+        node
+    }
+
+    /// Remove a node from the client and close its connection.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The ID of the node.
+    /// - `drain`: Whether to move the players of the node to the remaining nodes first,
+    ///   instead of deleting them.
+    ///
+    /// The node stops handling its websocket messages as soon as it is removed, so events it
+    /// receives while its players are being drained are not dispatched.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::NodeNotFound` if no node has this ID, and
+    /// `LavalinkError::LastNode` if it is the only node left.
+    pub async fn remove_node(&self, id: usize, drain: bool) -> LavalinkResult<()> {
+        let mut removed = Err(LavalinkError::NodeNotFound(id));
+
+        // Checking inside the update keeps concurrent removals from removing every node.
+        self.nodes.rcu(|nodes| {
+            let Some(node) = nodes.iter().find(|x| x.id == id) else {
+                removed = Err(LavalinkError::NodeNotFound(id));
+                return nodes.to_vec();
+            };
+
+            if nodes.len() == 1 {
+                removed = Err(LavalinkError::LastNode);
+                return nodes.to_vec();
+            }
+
+            removed = Ok(node.clone());
+
+            nodes
+                .iter()
+                .filter(|x| x.id != id)
+                .cloned()
+                .collect::<Vec<_>>()
+        });
+
+        let node = removed?;
+
+        node.is_removed.store(true, Ordering::SeqCst);
+
+        let guilds = self
+            .players
+            .iter()
+            .filter(|x| x.1.id == id)
+            .map(|x| *x.key())
+            .collect::<Vec<_>>();
+
+        for guild_id in guilds {
+            let result = if drain {
+                let candidates = self.nodes.load_full();
+                let new_node = self.select_node(guild_id, &candidates).await;
+
+                self.migrate_player(
+                    guild_id,
+                    new_node,
+                    events::MigrationReason::NodeRemoved,
+                    None,
+                )
+                .await
+                .map(|_| ())
+            } else {
+                self.delete_player(guild_id).await
+            };
+
+            if let Err(why) = result {
+                error!(
+                    "Failed to remove player {:?} from node {}: {}",
+                    guild_id, id, why
+                );
+            }
+        }
+
+        if let Some(connection) = node.connection.swap(None) {
+            connection.abort();
+        }
+
+        node.http.cancel_requests();
+
+        if node.is_running.swap(false, Ordering::SeqCst) {
+            crate::node::EventDispatcher(&node, self)
+                .dispatch(
+                    events::NodeDisconnected {
+                        node_id: node.id,
+                        removed: true,
+                    },
+                    |e| e.node_disconnected,
+                )
+                .await;
+        }
+
+        info!("Removed node {} ({})", node.id, node.websocket_address);
+
+        Ok(())
     }
 
     /// Get the node assigned to a guild.
@@ -201,23 +400,20 @@ impl LavalinkClient {
 
         debug!("First time selecting node for guild {:?}", guild_id);
 
+        self.select_node(guild_id, &self.nodes.load()).await
+    }
+
+    /// Pick a node for the guild out of `nodes` using the configured distribution strategy.
+    ///
+    /// If a custom strategy returns a node that is not part of `nodes`, the first one is used.
+    async fn select_node(&self, guild_id: GuildId, nodes: &[Arc<node::Node>]) -> Arc<node::Node> {
         use client::NodeDistributionStrategy::*;
 
         match &self.strategy {
-            Sharded => self
-                .get_node_by_index(guild_id.0 as usize % self.nodes.len())
-                .unwrap(),
-            RoundRobin(x) => {
-                let mut idx = x.fetch_add(1, Ordering::SeqCst);
-                if idx == self.nodes.len() {
-                    x.store(1, Ordering::SeqCst);
-                    idx = 0;
-                }
-
-                self.get_node_by_index(idx).unwrap()
-            }
+            Sharded => nodes[guild_id.0 as usize % nodes.len()].clone(),
+            RoundRobin(x) => nodes[x.fetch_add(1, Ordering::SeqCst) % nodes.len()].clone(),
             MainFallback => {
-                for node in &*self.nodes {
+                for node in nodes {
                     if node.is_running.load(Ordering::SeqCst) {
                         return node.clone();
                     }
@@ -226,7 +422,7 @@ impl LavalinkClient {
                 warn!("No nodes are currently running, waiting 5 seconds and trying again...");
                 tokio::time::sleep(std::time::Duration::from_secs(5)).await;
 
-                for node in &*self.nodes {
+                for node in nodes {
                     if node.is_running.load(Ordering::SeqCst) {
                         return node.clone();
                     }
@@ -234,21 +430,54 @@ impl LavalinkClient {
 
                 warn!("No nodes are currently running, returning first node.");
 
-                self.get_node_by_index(0).unwrap()
+                nodes[0].clone()
+            }
+            LowestLoad | HighestFreeMemory | LowestPenalty => {
+                let candidates = Self::running_nodes(nodes);
+
+                let stats = candidates
+                    .iter()
+                    .map(|x| x.stats.load_full())
+                    .collect::<Vec<_>>();
+                let stats = stats.iter().map(|x| x.as_deref()).collect::<Vec<_>>();
+
+                let idx = self.strategy.select_by_stats(&stats).unwrap_or(0);
+
+                candidates[idx].clone()
+            }
+            ByRegion(regions) => {
+                let candidates = Self::running_nodes(nodes);
+
+                let stats = candidates
+                    .iter()
+                    .map(|x| x.stats.load_full())
+                    .collect::<Vec<_>>();
+                let candidate_regions = candidates
+                    .iter()
+                    .zip(&stats)
+                    .map(|(node, stats)| (node.regions.as_slice(), stats.as_deref()))
+                    .collect::<Vec<_>>();
+
+                let endpoint = self.voice_endpoints.get(&guild_id).map(|x| x.clone());
+                let idx = regions
+                    .select(endpoint.as_deref(), &candidate_regions)
+                    .unwrap_or(0);
+
+                candidates[idx].clone()
+            }
+            Custom(func) => {
+                let node = func(self, guild_id).await;
+
+                if nodes.iter().any(|x| x.id == node.id) {
+                    node
+                } else {
+                    debug!(
+                        "Custom strategy returned node {} which is not available, using node {} instead.",
+                        node.id, nodes[0].id
+                    );
+                    nodes[0].clone()
+                }
             }
-            LowestLoad => self
-                .nodes
-                .iter()
-                .min_by_key(|x| x.cpu.load().system_load.abs() as u8)
-                .unwrap()
-                .clone(),
-            HighestFreeMemory => self
-                .nodes
-                .iter()
-                .min_by_key(|x| x.memory.load().free)
-                .unwrap()
-                .clone(),
-            Custom(func) => func(self, guild_id).await,
             #[cfg(feature = "python")]
             CustomPython(func) => {
                 use pyo3::prelude::*;
@@ -302,6 +531,70 @@ impl LavalinkClient {
         }
     }
 
+    /// The running nodes out of `nodes`, or all of them if none is running.
+    ///
+    /// The stats of disconnected nodes are stale, so they are only used if nothing is running.
+    fn running_nodes(nodes: &[Arc<node::Node>]) -> Vec<Arc<node::Node>> {
+        let running = nodes
+            .iter()
+            .filter(|x| x.is_running.load(Ordering::SeqCst))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        if running.is_empty() {
+            nodes.to_vec()
+        } else {
+            running
+        }
+    }
+
+    /// Store the voice server endpoint of a guild.
+    ///
+    /// With `NodeDistributionStrategy::ByRegion`, if the guild already has a player and the
+    /// endpoint is in another region than the previous one, the player is moved to the node
+    /// selected for the new region, if it's a different one.
+    ///
+    /// The moved player connects to the new voice server instead of the one it was using.
+    async fn update_voice_endpoint(&self, guild_id: GuildId, voice_server: VoiceServer) {
+        let endpoint = voice_server.endpoint.clone();
+        let previous = self.voice_endpoints.insert(guild_id, endpoint.clone());
+
+        let client::NodeDistributionStrategy::ByRegion(_) = &self.strategy else {
+            return;
+        };
+
+        if previous.as_deref().and_then(client::voice_region) == client::voice_region(&endpoint) {
+            return;
+        }
+
+        let Some(old_node) = self.players.get(&guild_id).map(|x| x.1.clone()) else {
+            return;
+        };
+
+        let new_node = self.select_node(guild_id, &self.nodes.load()).await;
+
+        if new_node.id == old_node.id {
+            return;
+        }
+
+        debug!(
+            "Voice server of guild {:?} moved to {}, moving its player",
+            guild_id, endpoint
+        );
+
+        if let Err(why) = self
+            .migrate_player(
+                guild_id,
+                new_node,
+                events::MigrationReason::RegionChanged,
+                Some(voice_server),
+            )
+            .await
+        {
+            error!("Failed to move player {:?}: {}", guild_id, why);
+        }
+    }
+
     /// Get the player context for a guild, if it exists.
     pub fn get_player_context(&self, guild_id: impl Into<GuildId>) -> Option<PlayerContext> {
         let guild_id = guild_id.into();
@@ -325,6 +618,9 @@ impl LavalinkClient {
         let mut connection_info = connection_info.into();
         connection_info.fix();
 
+        self.update_voice_endpoint(guild_id, (&connection_info).into())
+            .await;
+
         let node = self.get_node_for_guild(guild_id).await;
 
         let player = node
@@ -340,9 +636,17 @@ impl LavalinkClient {
             )
             .await?;
 
-        self.players
-            .entry(guild_id)
-            .or_insert((ArcSwapOption::new(None), node));
+        let created = match self.players.entry(guild_id) {
+            Entry::Occupied(_) => false,
+            Entry::Vacant(x) => {
+                x.insert((ArcSwapOption::new(None), node.clone()));
+                true
+            }
+        };
+
+        if created {
+            self.dispatch_player_created(guild_id, &node).await;
+        }
 
         Ok(player)
     }
@@ -372,6 +676,9 @@ impl LavalinkClient {
         let mut connection_info = connection_info.into();
         connection_info.fix();
 
+        self.update_voice_endpoint(guild_id, (&connection_info).into())
+            .await;
+
         let node = self.get_node_for_guild(guild_id).await;
 
         if let Some(x) = self.players.get(&guild_id) {
@@ -408,15 +715,33 @@ impl LavalinkClient {
             player_data: player,
             dummy: player_dummy.clone(),
             last_should_continue: true,
+            repeat_mode: RepeatMode::Off,
+            current_track: None,
+            history: VecDeque::new(),
+            queue_revision: 0,
+            queue_tx: broadcast::channel(crate::player_context::QUEUE_CHANNEL_CAPACITY).0,
+            fade: None,
+            restore_volume: None,
+            track_started_at: 0,
+            ended_track: None,
+            event_tx: PlayerContextInner::spawn_event_task(self.clone(), guild_id),
         };
 
         player_context.start(rx).await;
 
-        self.players.insert(
+        let previous = self.players.insert(
             guild_id,
-            (ArcSwapOption::new(Some(player_dummy.clone().into())), node),
+            (
+                ArcSwapOption::new(Some(player_dummy.clone().into())),
+                node.clone(),
+            ),
         );
 
+        // A player created without a context already dispatched the event.
+        if previous.is_none() {
+            self.dispatch_player_created(guild_id, &node).await;
+        }
+
         Ok(player_dummy)
     }
 
@@ -425,17 +750,44 @@ impl LavalinkClient {
         let guild_id = guild_id.into();
         let node = self.get_node_for_guild(guild_id).await;
 
-        if let Some((_, (player, _))) = self.players.remove(&guild_id) {
+        let removed = self.players.remove(&guild_id);
+
+        if let Some((_, (player, _))) = &removed {
             if let Some(x) = &*player.load() {
                 (**x).clone().close()?;
             }
         }
 
-        node.http
+        let result = node
+            .http
             .delete_player(guild_id, &node.session_id.load())
-            .await?;
+            .await;
+
+        if removed.is_some() {
+            crate::node::EventDispatcher(&node, self)
+                .dispatch(
+                    events::PlayerDestroyed {
+                        guild_id,
+                        node_id: node.id,
+                    },
+                    |e| e.player_destroyed,
+                )
+                .await;
+        }
 
-        Ok(())
+        result
+    }
+
+    async fn dispatch_player_created(&self, guild_id: GuildId, node: &node::Node) {
+        crate::node::EventDispatcher(node, self)
+            .dispatch(
+                events::PlayerCreated {
+                    guild_id,
+                    node_id: node.id,
+                },
+                |e| e.player_created,
+            )
+            .await;
     }
 
     /// Deletes all stored player contexts.
@@ -498,6 +850,50 @@ impl LavalinkClient {
         Ok(result)
     }
 
+    /// Search for tracks, albums, artists, playlists and text suggestions.
+    ///
+    /// NOTE: Requires LavaSearch plugin.
+    ///
+    /// # Parameters
+    ///
+    /// - `query`: A search, like the ones made with `LavaSearchEngines`.
+    /// - `types`: The kinds of results to return, every kind if empty.
+    pub async fn load_search(
+        &self,
+        guild_id: impl Into<GuildId>,
+        query: &str,
+        types: &[search::SearchType],
+    ) -> LavalinkResult<search::SearchResult> {
+        let guild_id = guild_id.into();
+        let node = self.get_node_for_guild(guild_id).await;
+
+        let result = node.http.load_search(query, types).await?;
+
+        Ok(result)
+    }
+
+    /// Load the lyrics of a track.
+    ///
+    /// None if no lyrics were found.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    ///
+    /// # Parameters
+    ///
+    /// - `track`: base64 encoded track data.
+    pub async fn load_lyrics(
+        &self,
+        guild_id: impl Into<GuildId>,
+        track: &str,
+    ) -> LavalinkResult<Option<lyrics::Lyrics>> {
+        let guild_id = guild_id.into();
+        let node = self.get_node_for_guild(guild_id).await;
+
+        let result = node.http.load_lyrics(track, false).await?;
+
+        Ok(result)
+    }
+
     /// Decode a single track into its info.
     ///
     /// # Parameters
@@ -569,6 +965,41 @@ impl LavalinkClient {
         Ok(result)
     }
 
+    /// Request the status of the route planner of the node bound to the guild.
+    ///
+    /// None if the node has no route planner configured.
+    pub async fn request_route_planner_status(
+        &self,
+        guild_id: impl Into<GuildId>,
+    ) -> LavalinkResult<Option<http::RoutePlannerStatus>> {
+        let guild_id = guild_id.into();
+        let node = self.get_node_for_guild(guild_id).await;
+
+        let result = node.http.route_planner_status().await?;
+
+        Ok(result)
+    }
+
+    /// Unmark a failing address of the route planner of the node bound to the guild.
+    pub async fn free_address(
+        &self,
+        guild_id: impl Into<GuildId>,
+        address: &str,
+    ) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+        let node = self.get_node_for_guild(guild_id).await;
+
+        node.http.free_address(address).await
+    }
+
+    /// Unmark every failing address of the route planner of the node bound to the guild.
+    pub async fn free_all_addresses(&self, guild_id: impl Into<GuildId>) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+        let node = self.get_node_for_guild(guild_id).await;
+
+        node.http.free_all_addresses().await
+    }
+
     /// Returns the player for the guild.
     pub async fn request_player(
         &self,
@@ -598,6 +1029,367 @@ impl LavalinkClient {
         Ok(result)
     }
 
+    /// Configure resuming for a new session, and resynchronize the player contexts bound to the
+    /// node if the previous session was resumed.
+    pub(crate) async fn handle_ready(&self, node: &node::Node, ready: &events::Ready) {
+        if let Some(timeout) = self.options.resume_timeout {
+            if let Err(why) = node
+                .http
+                .set_resuming_state(
+                    &ready.session_id,
+                    &http::ResumingState {
+                        resuming: Some(true),
+                        timeout: Some(timeout.as_secs() as u32),
+                    },
+                )
+                .await
+            {
+                error!(
+                    "Failed to enable resuming for session {}: {}",
+                    ready.session_id, why
+                );
+            }
+        }
+
+        if !ready.resumed {
+            return;
+        }
+
+        debug!(
+            "Session {} resumed, resynchronizing players",
+            ready.session_id
+        );
+
+        let players = match node.http.get_players(&ready.session_id).await {
+            Ok(players) => players,
+            Err(why) => {
+                error!(
+                    "Failed to fetch players of resumed session {}: {}",
+                    ready.session_id, why
+                );
+                return;
+            }
+        };
+
+        for player in players {
+            let Some(player_context) = self
+                .players
+                .get(&player.guild_id)
+                .filter(|x| x.1.id == node.id)
+                .and_then(|x| x.0.load_full())
+            else {
+                continue;
+            };
+
+            if let Err(why) = player_context.update_player_data(player) {
+                error!(
+                    "Error resynchronizing player {}: {}",
+                    player_context.guild_id.0, why
+                );
+            }
+        }
+    }
+
+    /// Move the player of a guild to another node.
+    ///
+    /// The player is recreated on the new node with the stored connection information, track,
+    /// position, volume and filters, and then removed from the old node if it is still running.
+    /// Dispatches the `player_migrated` event once done.
+    ///
+    /// If the guild has no player context, the player data is requested from the old node, which
+    /// must be running for this to succeed.
+    pub async fn move_player(
+        &self,
+        guild_id: impl Into<GuildId>,
+        node: Arc<node::Node>,
+    ) -> LavalinkResult<player::Player> {
+        self.migrate_player(
+            guild_id.into(),
+            node,
+            events::MigrationReason::Requested,
+            None,
+        )
+        .await
+    }
+
+    /// Move the player of a guild to another node.
+    ///
+    /// If `voice_server` is provided, the new player connects to it instead of the voice server
+    /// the old player was connected to.
+    async fn migrate_player(
+        &self,
+        guild_id: GuildId,
+        new_node: Arc<node::Node>,
+        reason: events::MigrationReason,
+        voice_server: Option<VoiceServer>,
+    ) -> LavalinkResult<player::Player> {
+        let old_node = self.get_node_for_guild(guild_id).await;
+        let player_context = self.get_player_context(guild_id);
+
+        let player = if let Some(player_context) = &player_context {
+            player_context.get_player().await?
+        } else {
+            old_node
+                .http
+                .get_player(guild_id, &old_node.session_id.load())
+                .await?
+        };
+
+        let mut update_player = player.clone().into_update_player();
+
+        if let Some(voice_server) = voice_server {
+            update_player.voice = Some(player::ConnectionInfo {
+                token: voice_server.token,
+                endpoint: voice_server.endpoint,
+                session_id: voice_server
+                    .session_id
+                    .unwrap_or_else(|| player.voice.session_id.clone()),
+            });
+        }
+
+        let old_node_alive = old_node.is_running.load(Ordering::SeqCst);
+
+        debug!(
+            "Moving player {:?} from node {} to node {}",
+            guild_id, old_node.id, new_node.id
+        );
+
+        // Rebind before recreating so the events of the new player are not treated as stale.
+        self.players
+            .entry(guild_id)
+            .or_insert((ArcSwapOption::new(None), old_node.clone()))
+            .1 = new_node.clone();
+
+        let result = new_node
+            .http
+            .update_player(guild_id, &new_node.session_id.load(), &update_player, false)
+            .await;
+
+        let new_player = match result {
+            Ok(x) => x,
+            Err(why) => {
+                if old_node_alive {
+                    if let Some(mut x) = self.players.get_mut(&guild_id) {
+                        x.1 = old_node;
+                    }
+                }
+
+                return Err(why);
+            }
+        };
+
+        if let Some(player_context) = &player_context {
+            player_context.update_player_data(new_player.clone())?;
+        }
+
+        if old_node_alive && old_node.id != new_node.id {
+            if let Err(why) = old_node
+                .http
+                .delete_player(guild_id, &old_node.session_id.load())
+                .await
+            {
+                warn!(
+                    "Failed to delete player {:?} from node {} after moving it: {}",
+                    guild_id, old_node.id, why
+                );
+            }
+        }
+
+        crate::node::EventDispatcher(&new_node, self)
+            .dispatch(
+                events::PlayerMigrated {
+                    guild_id,
+                    old_node_id: old_node.id,
+                    new_node_id: new_node.id,
+                    reason,
+                },
+                |e| e.player_migrated,
+            )
+            .await;
+
+        Ok(new_player)
+    }
+
+    /// Called when the websocket connection of a node closes, or the first connection failed.
+    ///
+    /// Reconnects following the reconnect policy of the node, reporting every attempt with the
+    /// `node_reconnect` event. If the first attempt fails, every player bound to the node is moved
+    /// to a healthy one.
+    ///
+    /// This returns a boxed future because it reconnects the node, which in turn calls it again
+    /// once that connection is closed.
+    pub(crate) fn handle_node_lost(self, node_id: usize) -> BoxFuture<'static, ()> {
+        Box::pin(async move { self.reconnect_node(node_id).await })
+    }
+
+    async fn reconnect_node(&self, node_id: usize) {
+        let Some(node) = self.get_node_by_id(node_id) else {
+            return;
+        };
+
+        if node.is_reconnecting.swap(true, Ordering::SeqCst) {
+            trace!("Node {} is already reconnecting", node.id);
+            return;
+        }
+
+        let mut attempt = 0;
+
+        loop {
+            attempt += 1;
+
+            if node
+                .reconnect_policy
+                .max_attempts
+                .is_some_and(|max| attempt > max)
+            {
+                warn!(
+                    "Giving up on reconnecting to node {} after {} attempts.",
+                    node.id,
+                    attempt - 1
+                );
+
+                node.is_reconnecting.store(false, Ordering::SeqCst);
+                self.dispatch_node_reconnect(&node, attempt - 1, events::ReconnectStatus::GaveUp)
+                    .await;
+                return;
+            }
+
+            let delay = node.reconnect_policy.delay(attempt);
+            debug!(
+                "Reconnecting to node {} in {:?} (attempt {})",
+                node.id, delay, attempt
+            );
+            tokio::time::sleep(delay).await;
+
+            if node.is_removed.load(Ordering::SeqCst) {
+                debug!("Node {} was removed, no longer reconnecting", node.id);
+                node.is_reconnecting.store(false, Ordering::SeqCst);
+                return;
+            }
+
+            match node.connect(self.clone()).await {
+                Ok(()) => {
+                    info!(
+                        "Reconnected to node {} after {} attempts.",
+                        node.id, attempt
+                    );
+
+                    node.is_reconnecting.store(false, Ordering::SeqCst);
+                    self.dispatch_node_reconnect(
+                        &node,
+                        attempt,
+                        events::ReconnectStatus::Connected,
+                    )
+                    .await;
+
+                    // The connection may have closed before the flag was cleared, in which case
+                    // nobody else is going to reconnect it.
+                    if node.is_running.load(Ordering::SeqCst)
+                        || node.is_reconnecting.swap(true, Ordering::SeqCst)
+                    {
+                        return;
+                    }
+
+                    attempt = 0;
+                }
+                Err(why) => {
+                    error!(
+                        "Failed to reconnect to node {} (attempt {}): {}",
+                        node.id, attempt, why
+                    );
+
+                    self.dispatch_node_reconnect(
+                        &node,
+                        attempt,
+                        events::ReconnectStatus::Failed(why.to_string()),
+                    )
+                    .await;
+
+                    if attempt == 1 {
+                        tokio::spawn({
+                            let client = self.clone();
+                            let node = node.clone();
+                            async move { client.failover_players(&node).await }
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    async fn dispatch_node_reconnect(
+        &self,
+        node: &node::Node,
+        attempt: u32,
+        status: events::ReconnectStatus,
+    ) {
+        crate::node::EventDispatcher(node, self)
+            .dispatch(
+                events::NodeReconnect {
+                    node_id: node.id,
+                    attempt,
+                    status,
+                },
+                |e| e.node_reconnect,
+            )
+            .await;
+    }
+
+    /// Move every player bound to the node to a healthy one.
+    async fn failover_players(&self, node: &node::Node) {
+        let candidates = self
+            .nodes
+            .load()
+            .iter()
+            .filter(|x| x.id != node.id && x.is_running.load(Ordering::SeqCst))
+            .cloned()
+            .collect::<Vec<_>>();
+
+        let guilds = self
+            .players
+            .iter()
+            .filter(|x| x.1.id == node.id)
+            .map(|x| (*x.key(), x.0.load().is_some()))
+            .collect::<Vec<_>>();
+
+        if guilds.is_empty() {
+            return;
+        }
+
+        if candidates.is_empty() {
+            warn!(
+                "Node {} was lost, but there are no healthy nodes to move its players to.",
+                node.id
+            );
+            return;
+        }
+
+        for (guild_id, has_context) in guilds {
+            let new_node = self.select_node(guild_id, &candidates).await;
+
+            if !has_context {
+                // Without a context there is no stored player data to recreate it with.
+                warn!(
+                    "Player {:?} has no context, binding it to node {} without recreating it.",
+                    guild_id, new_node.id
+                );
+
+                if let Some(mut x) = self.players.get_mut(&guild_id) {
+                    x.1 = new_node;
+                }
+
+                continue;
+            }
+
+            if let Err(why) = self
+                .migrate_player(guild_id, new_node, events::MigrationReason::NodeLost, None)
+                .await
+            {
+                error!("Failed to move player {:?}: {}", guild_id, why);
+            }
+        }
+    }
+
     /// Get the custom data provided when creating the client.
     ///
     /// # Errors
@@ -764,9 +1556,28 @@ impl LavalinkClient {
                         });
                     }
 
-                    let mut entry = data.entry(guild_id).or_insert((None, None, None));
-                    let session_id = entry.value().2.clone();
-                    *entry.value_mut() = (Some(token), endpoint, session_id);
+                    let session_id = {
+                        let mut entry = data.entry(guild_id).or_insert((None, None, None));
+                        let session_id = entry.value().2.clone();
+                        *entry.value_mut() =
+                            (Some(token.clone()), endpoint.clone(), session_id.clone());
+                        session_id
+                    };
+
+                    // Spawned after storing the new voice server, so a player moved because of it
+                    // connects to the new one.
+                    if let Some(endpoint) = endpoint {
+                        let client = self.clone();
+                        let voice_server = VoiceServer {
+                            token,
+                            endpoint,
+                            session_id,
+                        };
+
+                        tokio::spawn(async move {
+                            client.update_voice_endpoint(guild_id, voice_server).await;
+                        });
+                    }
 
                     {
                         let inner_tx = &channels.get(&guild_id).unwrap().0;
@@ -821,3 +1632,60 @@ impl LavalinkClient {
         }
     }
 }
+
+/// A stream of events, created with `LavalinkClient::subscribe`.
+///
+/// Yields the ID of the node the event came from, and the event itself.
+pub struct EventStream {
+    stream: BoxStream<'static, (usize, events::LavalinkEvent)>,
+    guild_id: Option<GuildId>,
+    node_id: Option<usize>,
+}
+
+impl EventStream {
+    /// Only yield the events related to this guild.
+    pub fn guild(mut self, guild_id: impl Into<GuildId>) -> Self {
+        self.guild_id = Some(guild_id.into());
+        self
+    }
+
+    /// Only yield the events that came from this node.
+    pub fn node(mut self, node_id: usize) -> Self {
+        self.node_id = Some(node_id);
+        self
+    }
+
+    fn matches(&self, node_id: usize, event: &events::LavalinkEvent) -> bool {
+        (self.node_id.is_none() || self.node_id == Some(node_id))
+            && (self.guild_id.is_none() || event.guild_id() == self.guild_id)
+    }
+}
+
+impl std::fmt::Debug for EventStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventStream")
+            .field("guild_id", &self.guild_id)
+            .field("node_id", &self.node_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl futures::Stream for EventStream {
+    type Item = (usize, events::LavalinkEvent);
+
+    fn poll_next(
+        mut self: std::pin::Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Option<Self::Item>> {
+        loop {
+            match self.stream.as_mut().poll_next(cx) {
+                std::task::Poll::Ready(Some((node_id, event))) => {
+                    if self.matches(node_id, &event) {
+                        return std::task::Poll::Ready(Some((node_id, event)));
+                    }
+                }
+                x => return x,
+            }
+        }
+    }
+}
@@ -70,6 +70,14 @@ pub enum LavalinkError {
     TrackError(TrackError),
     InvalidDataType,
     Timeout,
+    NodeNotFound(usize),
+    LastNode,
+    Base64Error(base64::DecodeError),
+    InvalidEncodedTrack(String),
+    IndexOutOfBounds { index: usize, len: usize },
+    InvalidFilter(String),
+    Cancelled,
+    MissingUserData,
 }
 
 impl Error for LavalinkError {}
@@ -136,6 +144,34 @@ impl Display for LavalinkError {
             LavalinkError::Timeout => {
                 write!(f, "Timeout reached while waiting for response.")
             }
+            LavalinkError::NodeNotFound(id) => {
+                write!(f, "There is no node with the ID {}.", id)
+            }
+            LavalinkError::LastNode => {
+                write!(f, "The last node of the client cannot be removed.")
+            }
+            LavalinkError::Base64Error(why) => {
+                write!(f, "Error decoding base64 => {:?}", why)
+            }
+            LavalinkError::InvalidEncodedTrack(why) => {
+                write!(f, "Invalid encoded track: {}", why)
+            }
+            LavalinkError::IndexOutOfBounds { index, len } => {
+                write!(
+                    f,
+                    "The index {} is out of bounds for a queue of {} tracks.",
+                    index, len
+                )
+            }
+            LavalinkError::InvalidFilter(why) => {
+                write!(f, "Invalid filter: {}", why)
+            }
+            LavalinkError::Cancelled => {
+                write!(f, "The request was cancelled.")
+            }
+            LavalinkError::MissingUserData => {
+                write!(f, "The track has no user data.")
+            }
         }
     }
 }
@@ -218,6 +254,12 @@ impl From<serde_qs::Error> for LavalinkError {
     }
 }
 
+impl From<base64::DecodeError> for LavalinkError {
+    fn from(err: base64::DecodeError) -> Self {
+        LavalinkError::Base64Error(err)
+    }
+}
+
 impl From<serde_json::Error> for LavalinkError {
     fn from(err: serde_json::Error) -> Self {
         LavalinkError::SerdeErrorJson(err)
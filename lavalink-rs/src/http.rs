@@ -1,12 +1,23 @@
-use crate::error::LavalinkResult;
+use crate::error::{LavalinkError, LavalinkResult};
+use crate::model::client::RequestPolicy;
 use crate::model::*;
 
+use std::io::Read;
 use std::sync::Arc;
+use std::time::Duration;
 
-use ::http::{uri::InvalidUri, Method, Uri};
-use http_body_util::BodyExt;
+use ::http::{uri::InvalidUri, Method, StatusCode, Uri};
+use bytes::Bytes;
+use futures::future::{self, Either};
+use http_body_util::{BodyExt, Full};
 use hyper::{body::Buf, Request};
-use std::io::Read;
+
+/// The statuses that are worth retrying an idempotent request for.
+const RETRY_STATUSES: [StatusCode; 3] = [
+    StatusCode::TOO_MANY_REQUESTS,
+    StatusCode::BAD_GATEWAY,
+    StatusCode::SERVICE_UNAVAILABLE,
+];
 
 #[derive(Debug, Clone)]
 #[cfg_attr(feature = "python", pyo3::pyclass)]
@@ -21,10 +32,22 @@ pub struct Http {
             http_body_util::Full<bytes::Bytes>,
         >,
     >,
+    /// How long requests can take and how to retry them.
+    pub policy: RequestPolicy,
+    pub(crate) cancel: Arc<tokio::sync::Notify>,
+}
+
+/// A response that was received in full.
+struct Received {
+    status: StatusCode,
+    retry_after: Option<Duration>,
+    body: Bytes,
 }
 
 impl Http {
     /// Makes an HTTP/1.1 request using Hyper to endpoints that return deserializable data.
+    ///
+    /// GET requests are retried according to the request policy.
     pub async fn request<R: serde::de::DeserializeOwned, T: serde::Serialize + ?Sized, U>(
         &self,
         method: Method,
@@ -35,42 +58,168 @@ impl Http {
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<::http::Error>,
     {
-        let mut request_builder = Request::builder().method(method).uri(uri);
+        let idempotent = method == Method::GET;
 
-        {
-            *request_builder.headers_mut().unwrap() = self.headers.clone()
-        }
-
-        request_builder = request_builder.header(::http::header::HOST, &self.authority);
+        self.request_with_retries(method, uri, data, idempotent)
+            .await
+    }
 
-        let request = if let Some(data) = data {
-            request_builder =
-                request_builder.header(::http::header::CONTENT_TYPE, "application/json");
-            request_builder.body(http_body_util::Full::from(serde_json::to_vec(data)?))?
-        } else {
-            request_builder.body(http_body_util::Full::default())?
-        };
+    /// Makes an HTTP/1.1 request using Hyper to endpoints that return text data.
+    ///
+    /// GET requests are retried according to the request policy.
+    pub async fn raw_request<T: serde::Serialize + ?Sized, U>(
+        &self,
+        method: Method,
+        uri: U,
+        data: Option<&T>,
+    ) -> LavalinkResult<String>
+    where
+        Uri: TryFrom<U>,
+        <Uri as TryFrom<U>>::Error: Into<::http::Error>,
+    {
+        let idempotent = method == Method::GET;
+        let uri = Uri::try_from(uri).map_err(|x| LavalinkError::HttpError(x.into()))?;
+        let data = data.map(serde_json::to_vec).transpose()?;
 
-        let response = self.request_client.request(request).await?;
+        let response = self.send(method, uri, data, idempotent).await?;
 
-        let raw_body = response.collect().await?.aggregate();
-        let body = serde_json::from_reader(raw_body.reader())?;
+        let mut body = "".to_string();
+        response.body.reader().read_to_string(&mut body)?;
 
         Ok(body)
     }
 
-    /// Makes an HTTP/1.1 request using Hyper to endpoints that return text data.
-    pub async fn raw_request<T: serde::Serialize + ?Sized, U>(
+    /// Cancel every request to this node that is still running, making them fail with
+    /// `LavalinkError::Cancelled`.
+    ///
+    /// A single request can be cancelled by dropping its future instead.
+    pub fn cancel_requests(&self) {
+        self.cancel.notify_waiters();
+    }
+
+    async fn request_with_retries<R: serde::de::DeserializeOwned, T: serde::Serialize + ?Sized, U>(
         &self,
         method: Method,
         uri: U,
         data: Option<&T>,
-    ) -> LavalinkResult<String>
+        idempotent: bool,
+    ) -> LavalinkResult<R>
     where
         Uri: TryFrom<U>,
         <Uri as TryFrom<U>>::Error: Into<::http::Error>,
     {
-        let mut request_builder = Request::builder().method(method).uri(uri);
+        let uri = Uri::try_from(uri).map_err(|x| LavalinkError::HttpError(x.into()))?;
+        let data = data.map(serde_json::to_vec).transpose()?;
+
+        let response = self.send(method, uri, data, idempotent).await?;
+
+        Ok(serde_json::from_slice(&response.body)?)
+    }
+
+    /// Send the request until it succeeds, it fails in a way that can't be retried, or the
+    /// policy runs out of retries.
+    ///
+    /// Error responses are returned as they are, for the caller to deserialize.
+    async fn send(
+        &self,
+        method: Method,
+        uri: Uri,
+        data: Option<Vec<u8>>,
+        idempotent: bool,
+    ) -> LavalinkResult<Received> {
+        let cancelled = self.cancel.notified();
+        let mut cancelled = std::pin::pin!(cancelled);
+        let mut retry = 0;
+
+        loop {
+            let request = self.build_request(&method, &uri, data.as_deref())?;
+
+            let result =
+                match future::select(std::pin::pin!(self.attempt(request)), cancelled.as_mut())
+                    .await
+                {
+                    Either::Left((result, _)) => result,
+                    Either::Right(_) => return Err(LavalinkError::Cancelled),
+                };
+
+            let delay = match &result {
+                _ if !idempotent || retry >= self.policy.max_retries => None,
+                Ok(response) if RETRY_STATUSES.contains(&response.status) => Some(
+                    response
+                        .retry_after
+                        .map(|x| x.min(self.policy.max_delay))
+                        .unwrap_or_else(|| self.policy.delay(retry + 1)),
+                ),
+                Err(LavalinkError::Timeout | LavalinkError::HyperClientError(_)) => {
+                    Some(self.policy.delay(retry + 1))
+                }
+                _ => None,
+            };
+
+            let Some(delay) = delay else {
+                return result;
+            };
+
+            retry += 1;
+
+            match &result {
+                Ok(response) => warn!(
+                    "{} {} returned {}, retrying in {:?} ({}/{})",
+                    method, uri, response.status, delay, retry, self.policy.max_retries
+                ),
+                Err(why) => warn!(
+                    "{} {} failed: {}, retrying in {:?} ({}/{})",
+                    method, uri, why, delay, retry, self.policy.max_retries
+                ),
+            }
+
+            if let Either::Right(_) = future::select(
+                std::pin::pin!(tokio::time::sleep(delay)),
+                cancelled.as_mut(),
+            )
+            .await
+            {
+                return Err(LavalinkError::Cancelled);
+            }
+        }
+    }
+
+    /// Send the request once and read the whole response, within the timeout of the policy.
+    async fn attempt(&self, request: Request<Full<Bytes>>) -> LavalinkResult<Received> {
+        let attempt = async {
+            let response = self.request_client.request(request).await?;
+
+            let status = response.status();
+            let retry_after = response
+                .headers()
+                .get(::http::header::RETRY_AFTER)
+                .and_then(|x| x.to_str().ok())
+                .and_then(|x| x.trim().parse::<u64>().ok())
+                .map(Duration::from_secs);
+            let body = response.collect().await?.to_bytes();
+
+            Ok(Received {
+                status,
+                retry_after,
+                body,
+            })
+        };
+
+        match self.policy.timeout {
+            Some(timeout) => tokio::time::timeout(timeout, attempt)
+                .await
+                .map_err(|_| LavalinkError::Timeout)?,
+            None => attempt.await,
+        }
+    }
+
+    fn build_request(
+        &self,
+        method: &Method,
+        uri: &Uri,
+        data: Option<&[u8]>,
+    ) -> LavalinkResult<Request<Full<Bytes>>> {
+        let mut request_builder = Request::builder().method(method.clone()).uri(uri.clone());
 
         {
             *request_builder.headers_mut().unwrap() = self.headers.clone()
@@ -81,18 +230,12 @@ impl Http {
         let request = if let Some(data) = data {
             request_builder =
                 request_builder.header(::http::header::CONTENT_TYPE, "application/json");
-            request_builder.body(http_body_util::Full::from(serde_json::to_vec(data)?))?
+            request_builder.body(Full::from(Bytes::copy_from_slice(data)))?
         } else {
-            request_builder.body(http_body_util::Full::default())?
+            request_builder.body(Full::default())?
         };
 
-        let response = self.request_client.request(request).await?;
-
-        let mut body = "".to_string();
-        let raw_body = response.collect().await?.aggregate();
-        raw_body.reader().read_to_string(&mut body)?;
-
-        Ok(body)
+        Ok(request)
     }
 
     /// Convert a path and query to a uri that points to the lavalink server.
@@ -198,6 +341,46 @@ impl Http {
         }
     }
 
+    /// Search for tracks, albums, artists, playlists and text suggestions.
+    ///
+    /// NOTE: Requires LavaSearch plugin.
+    ///
+    /// # Parameters
+    ///
+    /// - `query`: A search, like the ones made with `LavaSearchEngines`.
+    /// - `types`: The kinds of results to return, every kind if empty.
+    pub async fn load_search(
+        &self,
+        query: &str,
+        types: &[search::SearchType],
+    ) -> LavalinkResult<search::SearchResult> {
+        let mut path = format!("/loadsearch?query={}", urlencoding::encode(query));
+
+        if !types.is_empty() {
+            let types = types
+                .iter()
+                .map(|x| x.to_string())
+                .collect::<Vec<_>>()
+                .join(",");
+
+            path.push_str(&format!("&types={}", types));
+        }
+
+        let response = self
+            .raw_request(Method::GET, self.path_to_uri(&path, true)?, None::<&()>)
+            .await?;
+
+        // Nothing was found.
+        if response.is_empty() {
+            return Ok(Default::default());
+        }
+
+        let result =
+            serde_json::from_str::<crate::error::RequestResult<_>>(&response)?.to_result()?;
+
+        Ok(result)
+    }
+
     /// Request Lavalink server version.
     pub async fn version(&self) -> LavalinkResult<String> {
         let response = self
@@ -266,11 +449,13 @@ impl Http {
     ///
     /// - `tracks`: base64 encoded tracks.
     pub async fn decode_tracks(&self, tracks: &[String]) -> LavalinkResult<Vec<track::TrackData>> {
+        // Decoding has no side effects, so it can be retried despite being a POST.
         let response = self
-            .request::<crate::error::RequestResult<_>, _, _>(
+            .request_with_retries::<crate::error::RequestResult<_>, _, _>(
                 Method::POST,
                 self.path_to_uri("/decodetracks", true)?,
                 Some(tracks),
+                true,
             )
             .await?
             .to_result()?;
@@ -314,4 +499,271 @@ impl Http {
 
         Ok(response)
     }
+
+    /// Load the lyrics of the track playing on the player of this guild.
+    ///
+    /// None if no lyrics were found.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    ///
+    /// # Parameters
+    ///
+    /// - `skip_track_source`: Don't use the source of the track to search for lyrics, only the
+    ///   lyrics sources.
+    pub async fn get_player_lyrics(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+        skip_track_source: bool,
+    ) -> LavalinkResult<Option<lyrics::Lyrics>> {
+        let guild_id = guild_id.into();
+
+        let uri = self.path_to_uri(
+            &format!(
+                "/sessions/{}/players/{}/track/lyrics?skipTrackSource={}",
+                session_id, guild_id.0, skip_track_source
+            ),
+            true,
+        )?;
+
+        let response = self.raw_request(Method::GET, uri, None::<&()>).await?;
+
+        optional_content(&response)
+    }
+
+    /// Load the lyrics of a track.
+    ///
+    /// None if no lyrics were found.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    ///
+    /// # Parameters
+    ///
+    /// - `track`: base64 encoded track data.
+    /// - `skip_track_source`: Don't use the source of the track to search for lyrics, only the
+    ///   lyrics sources.
+    pub async fn load_lyrics(
+        &self,
+        track: &str,
+        skip_track_source: bool,
+    ) -> LavalinkResult<Option<lyrics::Lyrics>> {
+        let uri = self.path_to_uri(
+            &format!(
+                "/lyrics?track={}&skipTrackSource={}",
+                urlencoding::encode(track),
+                skip_track_source
+            ),
+            true,
+        )?;
+
+        let response = self.raw_request(Method::GET, uri, None::<&()>).await?;
+
+        optional_content(&response)
+    }
+
+    /// Subscribe to the lyrics of the player of this guild, so the lyrics events are sent for
+    /// every track it plays.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub async fn subscribe_lyrics(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+        skip_track_source: bool,
+    ) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+
+        let uri = self.path_to_uri(
+            &format!(
+                "/sessions/{}/players/{}/lyrics/subscribe?skipTrackSource={}",
+                session_id, guild_id.0, skip_track_source
+            ),
+            true,
+        )?;
+
+        let response = self.raw_request(Method::POST, uri, None::<&()>).await?;
+
+        no_content(&response)
+    }
+
+    /// Stop sending the lyrics events for the player of this guild.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub async fn unsubscribe_lyrics(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+    ) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+
+        let uri = self.path_to_uri(
+            &format!(
+                "/sessions/{}/players/{}/lyrics/subscribe",
+                session_id, guild_id.0
+            ),
+            true,
+        )?;
+
+        let response = self.raw_request(Method::DELETE, uri, None::<&()>).await?;
+
+        no_content(&response)
+    }
+
+    /// Returns the SponsorBlock categories skipped by the player of this guild.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn get_sponsorblock_categories(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+    ) -> LavalinkResult<Vec<sponsorblock::SegmentCategory>> {
+        let guild_id = guild_id.into();
+
+        let response = self
+            .request::<crate::error::RequestResult<_>, _, _>(
+                Method::GET,
+                self.path_to_uri(
+                    &format!(
+                        "/sessions/{}/players/{}/sponsorblock/categories",
+                        session_id, guild_id.0
+                    ),
+                    true,
+                )?,
+                None::<&()>,
+            )
+            .await?
+            .to_result()?;
+
+        Ok(response)
+    }
+
+    /// Set the SponsorBlock categories skipped by the player of this guild.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn set_sponsorblock_categories(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+        categories: &[sponsorblock::SegmentCategory],
+    ) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+
+        let response = self
+            .raw_request(
+                Method::PUT,
+                self.path_to_uri(
+                    &format!(
+                        "/sessions/{}/players/{}/sponsorblock/categories",
+                        session_id, guild_id.0
+                    ),
+                    true,
+                )?,
+                Some(categories),
+            )
+            .await?;
+
+        no_content(&response)
+    }
+
+    /// Stop skipping SponsorBlock segments on the player of this guild.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn delete_sponsorblock_categories(
+        &self,
+        guild_id: impl Into<GuildId>,
+        session_id: &str,
+    ) -> LavalinkResult<()> {
+        let guild_id = guild_id.into();
+
+        let response = self
+            .raw_request(
+                Method::DELETE,
+                self.path_to_uri(
+                    &format!(
+                        "/sessions/{}/players/{}/sponsorblock/categories",
+                        session_id, guild_id.0
+                    ),
+                    true,
+                )?,
+                None::<&()>,
+            )
+            .await?;
+
+        no_content(&response)
+    }
+
+    /// Request the status of the route planner.
+    ///
+    /// None if the node has no route planner configured.
+    pub async fn route_planner_status(&self) -> LavalinkResult<Option<http::RoutePlannerStatus>> {
+        let response = self
+            .raw_request(
+                Method::GET,
+                self.path_to_uri("/routeplanner/status", true)?,
+                None::<&()>,
+            )
+            .await?;
+
+        if response.is_empty() {
+            return Ok(None);
+        }
+
+        let value = serde_json::from_str::<serde_json::Value>(&response)?;
+
+        // Older versions respond with a null class instead of no content.
+        if value.get("class").is_some_and(|x| x.is_null()) {
+            return Ok(None);
+        }
+
+        let status =
+            serde_json::from_value::<crate::error::RequestResult<_>>(value)?.to_result()?;
+
+        Ok(Some(status))
+    }
+
+    /// Unmark a failing address so the route planner can use it again.
+    pub async fn free_address(&self, address: &str) -> LavalinkResult<()> {
+        let response = self
+            .raw_request(
+                Method::POST,
+                self.path_to_uri("/routeplanner/free/address", true)?,
+                Some(&serde_json::json!({ "address": address })),
+            )
+            .await?;
+
+        no_content(&response)
+    }
+
+    /// Unmark every failing address so the route planner can use them again.
+    pub async fn free_all_addresses(&self) -> LavalinkResult<()> {
+        let response = self
+            .raw_request(
+                Method::POST,
+                self.path_to_uri("/routeplanner/free/all", true)?,
+                None::<&()>,
+            )
+            .await?;
+
+        no_content(&response)
+    }
+}
+
+/// Deserialize the body of an endpoint that responds with no content when there's no result.
+fn optional_content<T: serde::de::DeserializeOwned>(response: &str) -> LavalinkResult<Option<T>> {
+    if response.is_empty() {
+        return Ok(None);
+    }
+
+    let result = serde_json::from_str::<crate::error::RequestResult<_>>(response)?.to_result()?;
+
+    Ok(Some(result))
+}
+
+/// Check the body of an endpoint that responds with no content when it succeeds.
+fn no_content(response: &str) -> LavalinkResult<()> {
+    if response.is_empty() {
+        Ok(())
+    } else {
+        Err(serde_json::from_str::<crate::error::ResponseError>(response)?.into())
+    }
 }
@@ -38,6 +38,9 @@ pub mod node;
 pub mod player_context;
 /// Re-exports of all the most common types.
 pub mod prelude;
+/// A mock Lavalink server to write tests against.
+#[cfg(feature = "testing")]
+pub mod testing;
 /// Macros that abstract annoying stuff.
 #[cfg(feature = "macros")]
 pub mod macros {
@@ -16,15 +16,185 @@ pub(crate) enum ClientMessage {
     StateUpdate(GuildId, Option<ChannelId>, UserId, String), // guild_id, channel_id, user_id, session_id
 }
 
+#[derive(Debug, Default, Clone)]
+/// Client-wide settings that apply to every node.
+pub struct ClientOptions {
+    /// Enable session resuming with this timeout.
+    ///
+    /// When set, every new session is configured with `set_resuming_state`, and the stored
+    /// `Session-Id` is sent when reconnecting. If Lavalink reports the session as resumed, the
+    /// player contexts bound to that node are resynchronized from the server.
+    ///
+    /// None disables resuming, which is the default.
+    pub resume_timeout: Option<std::time::Duration>,
+    /// How to reconnect to nodes that lost their connection.
+    ///
+    /// Nodes can override it with `NodeBuilder::reconnect_policy`.
+    pub reconnect_policy: ReconnectPolicy,
+    /// How long REST requests can take and how to retry them.
+    ///
+    /// Nodes can override it with `NodeBuilder::request_policy`.
+    pub request_policy: RequestPolicy,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How and for how long to try reconnecting to a node.
+///
+/// The delay before each attempt starts at `initial_delay` and gets multiplied by `multiplier`
+/// after every failed attempt, up to `max_delay`.
+pub struct ReconnectPolicy {
+    /// The delay before the first attempt.
+    pub initial_delay: std::time::Duration,
+    /// The upper bound of the delay between attempts.
+    pub max_delay: std::time::Duration,
+    /// The factor the delay grows by after every failed attempt.
+    pub multiplier: f64,
+    /// The random variation applied to every delay, as a fraction of it (0.0 <= x <= 1.0)
+    ///
+    /// 0.1 means every delay is randomly shortened or lengthened by up to 10%.
+    pub jitter: f64,
+    /// The amount of attempts before giving up on the node.
+    ///
+    /// None retries forever.
+    pub max_attempts: Option<u32>,
+}
+
+impl Default for ReconnectPolicy {
+    fn default() -> Self {
+        Self {
+            initial_delay: std::time::Duration::from_secs(1),
+            max_delay: std::time::Duration::from_secs(60),
+            multiplier: 2.0,
+            jitter: 0.1,
+            max_attempts: None,
+        }
+    }
+}
+
+impl std::hash::Hash for ReconnectPolicy {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.initial_delay.hash(state);
+        self.max_delay.hash(state);
+        self.multiplier.to_bits().hash(state);
+        self.jitter.to_bits().hash(state);
+        self.max_attempts.hash(state);
+    }
+}
+
+impl ReconnectPolicy {
+    /// The delay to wait before the given attempt, starting at 1.
+    pub fn delay(&self, attempt: u32) -> std::time::Duration {
+        backoff(
+            self.initial_delay,
+            self.max_delay,
+            self.multiplier,
+            self.jitter,
+            attempt,
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// How long REST requests can take, and how to retry the idempotent ones.
+///
+/// Only requests that can safely be sent twice are retried, like loading and decoding tracks or
+/// getting the info and the players, and only when they time out, fail to connect, or get a
+/// 429, 502 or 503 response. The delay before each retry follows the same rules as
+/// `ReconnectPolicy`, unless the response has a `Retry-After` header.
+pub struct RequestPolicy {
+    /// How long a single attempt can take before failing with `LavalinkError::Timeout`.
+    ///
+    /// None waits forever.
+    pub timeout: Option<std::time::Duration>,
+    /// The amount of retries after the first attempt, 0 disables retrying.
+    pub max_retries: u32,
+    /// The delay before the first retry.
+    pub initial_delay: std::time::Duration,
+    /// The upper bound of the delay between retries, including the `Retry-After` ones.
+    pub max_delay: std::time::Duration,
+    /// The factor the delay grows by after every retry.
+    pub multiplier: f64,
+    /// The random variation applied to every delay, as a fraction of it (0.0 <= x <= 1.0)
+    pub jitter: f64,
+}
+
+impl Default for RequestPolicy {
+    fn default() -> Self {
+        Self {
+            timeout: Some(std::time::Duration::from_secs(10)),
+            max_retries: 3,
+            initial_delay: std::time::Duration::from_millis(250),
+            max_delay: std::time::Duration::from_secs(5),
+            multiplier: 2.0,
+            jitter: 0.1,
+        }
+    }
+}
+
+impl std::hash::Hash for RequestPolicy {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.timeout.hash(state);
+        self.max_retries.hash(state);
+        self.initial_delay.hash(state);
+        self.max_delay.hash(state);
+        self.multiplier.to_bits().hash(state);
+        self.jitter.to_bits().hash(state);
+    }
+}
+
+impl RequestPolicy {
+    /// The delay to wait before the given retry, starting at 1.
+    pub fn delay(&self, retry: u32) -> std::time::Duration {
+        backoff(
+            self.initial_delay,
+            self.max_delay,
+            self.multiplier,
+            self.jitter,
+            retry,
+        )
+    }
+}
+
+fn backoff(
+    initial_delay: std::time::Duration,
+    max_delay: std::time::Duration,
+    multiplier: f64,
+    jitter: f64,
+    attempt: u32,
+) -> std::time::Duration {
+    let exponent = attempt.saturating_sub(1).min(i32::MAX as u32) as i32;
+    let delay = (initial_delay.as_secs_f64() * multiplier.max(1.0).powi(exponent))
+        .min(max_delay.as_secs_f64());
+
+    let jitter = jitter.clamp(0.0, 1.0);
+    let factor = if jitter > 0.0 {
+        1.0 + rand::Rng::gen_range(&mut rand::thread_rng(), -jitter..=jitter)
+    } else {
+        1.0
+    };
+
+    std::time::Duration::from_secs_f64((delay * factor).max(0.0))
+}
+
 #[derive(Debug, Default, Clone)]
 pub enum NodeDistributionStrategy {
     #[default]
     Sharded,
     RoundRobin(Arc<AtomicUsize>),
     MainFallback,
+    /// Pick the node with the lowest system CPU load.
     LowestLoad,
+    /// Pick the node with the most free memory.
     HighestFreeMemory,
-    //ByRegion(...),
+    /// Pick the node with the lowest `Stats::penalty`, which accounts for the playing players,
+    /// the CPU load and the frames that were not sent on time.
+    LowestPenalty,
+    /// Pick the node with the lowest `Stats::penalty` out of the ones closest to the Discord voice
+    /// server of the guild, according to the region map and `NodeBuilder::regions`.
+    ///
+    /// Falls back to all the nodes if none of them is in a nearby region, or the voice server is
+    /// unknown. Players are moved when their voice server changes region.
+    ByRegion(Arc<RegionMap>),
     Custom(fn(&'_ crate::client::LavalinkClient, GuildId) -> BoxFuture<Arc<crate::node::Node>>),
     #[cfg(feature = "python")]
     CustomPython(PyObject),
@@ -55,9 +225,315 @@ impl NodeDistributionStrategy {
         Self::HighestFreeMemory
     }
 
+    pub fn lowest_penalty() -> Self {
+        Self::LowestPenalty
+    }
+
+    /// Select nodes by region, using `RegionMap::default` to find the nearby ones.
+    pub fn by_region() -> Self {
+        Self::ByRegion(Arc::new(RegionMap::default()))
+    }
+
+    pub fn by_region_with(regions: RegionMap) -> Self {
+        Self::ByRegion(Arc::new(regions))
+    }
+
     pub fn custom(
         func: fn(&'_ crate::client::LavalinkClient, GuildId) -> BoxFuture<Arc<crate::node::Node>>,
     ) -> NodeDistributionStrategy {
         NodeDistributionStrategy::Custom(func)
     }
+
+    /// Pick a node for the strategies based on the stats of the nodes, returning its position in
+    /// `stats`, which holds the last stats received from each node.
+    ///
+    /// Nodes that did not send stats yet are considered idle, but with no free memory. Returns
+    /// None if `stats` is empty or the strategy is not based on stats.
+    pub fn select_by_stats(&self, stats: &[Option<&events::Stats>]) -> Option<usize> {
+        let key: fn(Option<&events::Stats>) -> f64 = match self {
+            Self::LowestLoad => |x| x.map_or(0.0, |x| x.cpu.system_load),
+            Self::HighestFreeMemory => |x| x.map_or(0.0, |x| -(x.memory.free as f64)),
+            Self::LowestPenalty => |x| x.map_or(0.0, |x| x.penalty()),
+            _ => return None,
+        };
+
+        stats
+            .iter()
+            .enumerate()
+            .min_by(|(_, a), (_, b)| key(**a).total_cmp(&key(**b)))
+            .map(|(idx, _)| idx)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Maps Discord voice regions to the node regions closest to them.
+///
+/// The default map knows the Discord voice regions, mapping them to the node regions
+/// "us-east", "us-central", "us-south", "us-west", "south-america", "europe", "middle-east",
+/// "africa", "india", "asia" and "oceania", the closest one first.
+pub struct RegionMap {
+    pub regions: std::collections::HashMap<String, Vec<String>>,
+}
+
+impl RegionMap {
+    /// A map without any region, so every node is a fallback.
+    pub fn empty() -> Self {
+        Self {
+            regions: Default::default(),
+        }
+    }
+
+    /// Set the node regions nearby a voice region, the closest one first.
+    pub fn insert(
+        &mut self,
+        voice_region: impl Into<String>,
+        node_regions: impl IntoIterator<Item = impl Into<String>>,
+    ) -> &mut Self {
+        self.regions.insert(
+            voice_region.into(),
+            node_regions.into_iter().map(Into::into).collect(),
+        );
+        self
+    }
+
+    /// The node regions nearby the voice server endpoint, the closest one first.
+    pub fn nearby(&self, endpoint: &str) -> &[String] {
+        voice_region(endpoint)
+            .and_then(|x| self.regions.get(x))
+            .map_or(&[], |x| x.as_slice())
+    }
+
+    /// Pick a node for a voice server endpoint out of the regions and last stats of each node,
+    /// returning its position.
+    ///
+    /// The nodes in the closest region are preferred, picking the one with the lowest
+    /// `Stats::penalty` between them. Returns None if there are no nodes.
+    pub fn select(
+        &self,
+        endpoint: Option<&str>,
+        nodes: &[(&[String], Option<&events::Stats>)],
+    ) -> Option<usize> {
+        let by_penalty = |candidates: Vec<usize>| {
+            let stats = candidates.iter().map(|x| nodes[*x].1).collect::<Vec<_>>();
+
+            NodeDistributionStrategy::LowestPenalty
+                .select_by_stats(&stats)
+                .map(|x| candidates[x])
+        };
+
+        for region in endpoint.map_or(&[][..], |x| self.nearby(x)) {
+            let candidates = (0..nodes.len())
+                .filter(|x| nodes[*x].0.contains(region))
+                .collect::<Vec<_>>();
+
+            if !candidates.is_empty() {
+                return by_penalty(candidates);
+            }
+        }
+
+        by_penalty((0..nodes.len()).collect())
+    }
+}
+
+impl Default for RegionMap {
+    fn default() -> Self {
+        const US_EAST: &[&str] = &["us-east", "us-central", "us-south", "us-west", "europe"];
+        const US_CENTRAL: &[&str] = &["us-central", "us-east", "us-south", "us-west"];
+        const US_SOUTH: &[&str] = &["us-south", "us-central", "us-east", "us-west"];
+        const US_WEST: &[&str] = &["us-west", "us-central", "us-south", "us-east"];
+        const SOUTH_AMERICA: &[&str] = &["south-america", "us-south", "us-east"];
+        const EUROPE: &[&str] = &["europe", "middle-east", "us-east"];
+        const MIDDLE_EAST: &[&str] = &["middle-east", "europe", "india"];
+        const AFRICA: &[&str] = &["africa", "europe", "middle-east"];
+        const INDIA: &[&str] = &["india", "asia", "middle-east", "europe"];
+        const ASIA: &[&str] = &["asia", "oceania", "india", "us-west"];
+        const OCEANIA: &[&str] = &["oceania", "asia", "us-west"];
+
+        let mut map = Self::empty();
+
+        for (voice_regions, node_regions) in [
+            (&["us-east", "newark", "atlanta", "montreal"][..], US_EAST),
+            (&["us-central", "chicago"][..], US_CENTRAL),
+            (&["us-south", "dallas"][..], US_SOUTH),
+            (
+                &["us-west", "seattle", "santa-clara", "oregon"][..],
+                US_WEST,
+            ),
+            (&["brazil", "buenos-aires", "santiago"][..], SOUTH_AMERICA),
+            (
+                &[
+                    "europe",
+                    "rotterdam",
+                    "amsterdam",
+                    "frankfurt",
+                    "london",
+                    "madrid",
+                    "milan",
+                    "bucharest",
+                    "stockholm",
+                    "finland",
+                    "russia",
+                    "st-pete",
+                ][..],
+                EUROPE,
+            ),
+            (&["dubai", "tel-aviv"][..], MIDDLE_EAST),
+            (&["southafrica", "south-africa"][..], AFRICA),
+            (&["india", "mumbai"][..], INDIA),
+            (
+                &["japan", "hongkong", "singapore", "south-korea", "seoul"][..],
+                ASIA,
+            ),
+            (&["sydney", "australia"][..], OCEANIA),
+        ] {
+            for voice_region in voice_regions {
+                map.insert(*voice_region, node_regions.iter().copied());
+            }
+        }
+
+        map
+    }
+}
+
+/// Get the Discord voice region out of a voice server endpoint.
+///
+/// "rotterdam1234.discord.media:443" is in "rotterdam", and "us-east123.discord.gg" in "us-east".
+pub fn voice_region(endpoint: &str) -> Option<&str> {
+    let host = endpoint
+        .trim_start_matches("wss://")
+        .split(['.', ':'])
+        .next()?;
+    let region = host
+        .trim_end_matches(|x: char| x.is_ascii_digit())
+        .trim_end_matches('-');
+
+    (!region.is_empty()).then_some(region)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(playing_players: u64, system_load: f64, free: u64, deficit: i64) -> events::Stats {
+        events::Stats {
+            op: "stats".to_string(),
+            players: playing_players,
+            playing_players,
+            uptime: 0,
+            memory: events::Memory {
+                free,
+                ..Default::default()
+            },
+            cpu: events::Cpu {
+                cores: 4,
+                system_load,
+                lavalink_load: 0.0,
+            },
+            frame_stats: (playing_players > 0).then_some(events::FrameStats {
+                sent: 3000,
+                nulled: 0,
+                deficit,
+            }),
+        }
+    }
+
+    #[test]
+    fn penalty_grows_with_the_load() {
+        assert_eq!(stats(0, 0.0, 0, 0).penalty(), 0.0);
+
+        let busy = stats(10, 0.0, 0, 0).penalty();
+        let loaded = stats(10, 0.5, 0, 0).penalty();
+        let lagging = stats(10, 0.5, 0, 300).penalty();
+
+        assert_eq!(busy, 10.0);
+        assert!(busy < loaded);
+        assert!(loaded < lagging);
+        // Sending too many frames is not penalized.
+        assert_eq!(stats(10, 0.5, 0, -300).penalty(), loaded);
+    }
+
+    #[test]
+    fn selects_nodes_by_stats() {
+        let a = stats(2, 0.3, 100, 0);
+        let b = stats(8, 0.25, 300, 900);
+        let c = stats(3, 0.35, 200, 0);
+        let nodes = [Some(&a), Some(&b), Some(&c)];
+
+        assert_eq!(
+            NodeDistributionStrategy::lowest_load().select_by_stats(&nodes),
+            Some(1)
+        );
+        assert_eq!(
+            NodeDistributionStrategy::highest_free_memory().select_by_stats(&nodes),
+            Some(1)
+        );
+        assert_eq!(
+            NodeDistributionStrategy::lowest_penalty().select_by_stats(&nodes),
+            Some(0)
+        );
+        assert_eq!(
+            NodeDistributionStrategy::lowest_penalty().select_by_stats(&[Some(&a), None]),
+            Some(1)
+        );
+        assert_eq!(
+            NodeDistributionStrategy::sharded().select_by_stats(&nodes),
+            None
+        );
+        assert_eq!(
+            NodeDistributionStrategy::lowest_penalty().select_by_stats(&[]),
+            None
+        );
+    }
+
+    #[test]
+    fn parses_voice_regions() {
+        assert_eq!(
+            voice_region("rotterdam1234.discord.media:443"),
+            Some("rotterdam")
+        );
+        assert_eq!(voice_region("us-east123.discord.gg"), Some("us-east"));
+        assert_eq!(voice_region("wss://japan42.discord.media"), Some("japan"));
+        assert_eq!(voice_region("1234.discord.media"), None);
+        assert_eq!(voice_region(""), None);
+    }
+
+    #[test]
+    fn selects_the_nearest_region() {
+        let regions = RegionMap::default();
+
+        let europe = ["europe".to_string()];
+        let us_east = ["us-east".to_string()];
+        let untagged: [String; 0] = [];
+
+        let idle = stats(0, 0.0, 0, 0);
+        let busy = stats(50, 0.5, 0, 0);
+
+        let nodes = [
+            (&europe[..], Some(&busy)),
+            (&europe[..], Some(&idle)),
+            (&us_east[..], Some(&idle)),
+            (&untagged[..], None),
+        ];
+
+        assert_eq!(
+            regions.select(Some("rotterdam1234.discord.media:443"), &nodes),
+            Some(1)
+        );
+        assert_eq!(
+            regions.select(Some("newark12.discord.media:443"), &nodes),
+            Some(2)
+        );
+        // Chicago has no node of its own, but us-east is the next closest.
+        assert_eq!(
+            regions.select(Some("chicago12.discord.media:443"), &nodes),
+            Some(2)
+        );
+        // Nothing is close to Japan, so any idle node will do.
+        assert_eq!(
+            regions.select(Some("japan12.discord.media:443"), &nodes),
+            Some(1)
+        );
+        assert_eq!(regions.select(None, &nodes), Some(1));
+        assert_eq!(regions.select(None, &[]), None);
+    }
 }
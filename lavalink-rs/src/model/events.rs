@@ -26,16 +26,112 @@ pub struct Events {
     /// Dispatched when an audio WebSocket to Discord is closed.
     pub websocket_closed:
         Option<fn(LavalinkClient, session_id: String, &WebSocketClosed) -> BoxFuture<()>>,
+    /// Dispatched when lyrics are found for a track that started playing, while subscribed to
+    /// the lyrics of the player.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub lyrics_found: Option<fn(LavalinkClient, session_id: String, &LyricsFound) -> BoxFuture<()>>,
+    /// Dispatched when no lyrics are found for a track that started playing, while subscribed to
+    /// the lyrics of the player.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub lyrics_not_found:
+        Option<fn(LavalinkClient, session_id: String, &LyricsNotFound) -> BoxFuture<()>>,
+    /// Dispatched when a line of timed lyrics is reached, while subscribed to the lyrics of the
+    /// player.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub lyrics_line: Option<fn(LavalinkClient, session_id: String, &LyricsLine) -> BoxFuture<()>>,
+    /// Dispatched when the SponsorBlock segments of a track that started playing are loaded.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub segments_loaded:
+        Option<fn(LavalinkClient, session_id: String, &SegmentsLoaded) -> BoxFuture<()>>,
+    /// Dispatched when a SponsorBlock segment is skipped.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub segment_skipped:
+        Option<fn(LavalinkClient, session_id: String, &SegmentSkipped) -> BoxFuture<()>>,
+    /// Dispatched when the chapters of a track that started playing are loaded.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub chapters_loaded:
+        Option<fn(LavalinkClient, session_id: String, &ChaptersLoaded) -> BoxFuture<()>>,
+    /// Dispatched when a chapter of the playing track starts.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub chapter_started:
+        Option<fn(LavalinkClient, session_id: String, &ChapterStarted) -> BoxFuture<()>>,
+    /// Dispatched when a player gets moved to another node, either because its node was lost,
+    /// or because `move_player` was called.
+    ///
+    /// The session ID is the one of the node the player was moved to.
+    pub player_migrated:
+        Option<fn(LavalinkClient, session_id: String, &PlayerMigrated) -> BoxFuture<()>>,
+    /// Dispatched after every attempt to reconnect to a node, and when giving up on it.
+    pub node_reconnect:
+        Option<fn(LavalinkClient, session_id: String, &NodeReconnect) -> BoxFuture<()>>,
+    /// Dispatched when a message received from Lavalink could not be parsed.
+    pub invalid_message:
+        Option<fn(LavalinkClient, session_id: String, &InvalidMessage) -> BoxFuture<()>>,
+    /// Dispatched when a player context finishes a track and its queue is empty.
+    pub queue_ended: Option<fn(LavalinkClient, session_id: String, &QueueEnded) -> BoxFuture<()>>,
+    /// Dispatched when tracks are added to the queue of a player context.
+    pub track_queued: Option<fn(LavalinkClient, session_id: String, &TrackQueued) -> BoxFuture<()>>,
+    /// Dispatched when a player is created on a guild that didn't have one.
+    pub player_created:
+        Option<fn(LavalinkClient, session_id: String, &PlayerCreated) -> BoxFuture<()>>,
+    /// Dispatched when a player is deleted with `delete_player`.
+    pub player_destroyed:
+        Option<fn(LavalinkClient, session_id: String, &PlayerDestroyed) -> BoxFuture<()>>,
+    /// Dispatched when the websocket connection to a node is established, including reconnects.
+    pub node_connected:
+        Option<fn(LavalinkClient, session_id: String, &NodeConnected) -> BoxFuture<()>>,
+    /// Dispatched when the websocket connection to a node closes, or the node is removed.
+    pub node_disconnected:
+        Option<fn(LavalinkClient, session_id: String, &NodeDisconnected) -> BoxFuture<()>>,
 
     #[cfg(feature = "python")]
     pub(crate) event_handler: Option<crate::python::event::EventHandler>,
 }
 
+// Serde consumes the `op` and `type` fields while selecting the variant of `IncomingMessage` and
+// `Event`, so the messages get them back from these defaults.
+macro_rules! tag_defaults {
+    ($($name:ident => $value:literal,)*) => {
+        $(
+            fn $name() -> String {
+                $value.to_string()
+            }
+        )*
+    };
+}
+
+tag_defaults! {
+    ready_op => "ready",
+    player_update_op => "playerUpdate",
+    stats_op => "stats",
+    event_op => "event",
+    track_start_type => "TrackStartEvent",
+    track_end_type => "TrackEndEvent",
+    track_exception_type => "TrackExceptionEvent",
+    track_stuck_type => "TrackStuckEvent",
+    web_socket_closed_type => "WebSocketClosedEvent",
+    lyrics_found_type => "LyricsFoundEvent",
+    lyrics_not_found_type => "LyricsNotFoundEvent",
+    lyrics_line_type => "LyricsLineEvent",
+    segments_loaded_type => "SegmentsLoaded",
+    segment_skipped_type => "SegmentSkipped",
+    chapters_loaded_type => "ChaptersLoaded",
+    chapter_started_type => "ChapterStarted",
+}
+
 #[derive(Hash, PartialEq, Eq, PartialOrd, Ord, Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched by Lavalink upon successful connection and authorization.
 pub struct Ready {
+    #[serde(default = "ready_op")]
     pub op: String,
     /// The lavalink session ID, used for some REST requests and resuming.
     pub session_id: String,
@@ -48,6 +144,7 @@ pub struct Ready {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched periodically with the current state of a player.
 pub struct PlayerUpdate {
+    #[serde(default = "player_update_op")]
     pub op: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -60,7 +157,7 @@ pub struct PlayerUpdate {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// A collection of statistics sent every minute.
 pub struct Stats {
-    #[serde(default)]
+    #[serde(default = "stats_op")]
     pub op: String,
     /// The amount of players connected to the node.
     pub players: u64,
@@ -78,6 +175,27 @@ pub struct Stats {
     pub frame_stats: Option<FrameStats>,
 }
 
+impl Stats {
+    /// How loaded the node is, using the same formula as the official Lavalink clients.
+    ///
+    /// It adds up the amount of playing players, a penalty that grows exponentially with the
+    /// system CPU load, and penalties for frames that were not sent on time or were nulled.
+    /// Lower is better, and an idle node has a penalty of 0.
+    pub fn penalty(&self) -> f64 {
+        let player_penalty = self.playing_players as f64;
+        let cpu_penalty = 1.05f64.powf(100.0 * self.cpu.system_load) * 10.0 - 10.0;
+
+        let frame_penalty = self.frame_stats.as_ref().map_or(0.0, |x| {
+            let deficit = 1.03f64.powf(500.0 * (x.deficit.max(0) as f64 / 3000.0)) * 600.0 - 600.0;
+            let nulled = (1.03f64.powf(500.0 * (x.nulled as f64 / 3000.0)) * 300.0 - 300.0) * 2.0;
+
+            deficit + nulled
+        });
+
+        player_penalty + cpu_penalty + frame_penalty
+    }
+}
+
 #[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
@@ -115,8 +233,9 @@ pub struct FrameStats {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched when a track starts playing.
 pub struct TrackStart {
+    #[serde(default = "event_op")]
     pub op: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default = "track_start_type")]
     pub event_type: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -130,8 +249,9 @@ pub struct TrackStart {
 /// Dispatched when a track ends.
 /// track_exception and track_stuck will also trigger this event.
 pub struct TrackEnd {
+    #[serde(default = "event_op")]
     pub op: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default = "track_end_type")]
     pub event_type: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -171,8 +291,9 @@ impl From<TrackEndReason> for bool {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched when a track throws an exception.
 pub struct TrackException {
+    #[serde(default = "event_op")]
     pub op: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default = "track_exception_type")]
     pub event_type: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -187,8 +308,9 @@ pub struct TrackException {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched when a track gets stuck while playing.
 pub struct TrackStuck {
+    #[serde(default = "event_op")]
     pub op: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default = "track_stuck_type")]
     pub event_type: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -203,8 +325,9 @@ pub struct TrackStuck {
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
 /// Dispatched when an audio WebSocket to Discord is closed.
 pub struct WebSocketClosed {
+    #[serde(default = "event_op")]
     pub op: String,
-    #[serde(rename = "type")]
+    #[serde(rename = "type", default = "web_socket_closed_type")]
     pub event_type: String,
     #[serde(deserialize_with = "deserialize_number_from_string")]
     pub guild_id: GuildId,
@@ -218,3 +341,688 @@ pub struct WebSocketClosed {
     /// Whether the connection was closed by Discord or not.
     pub by_remote: bool,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when lyrics are found for a track that started playing.
+///
+/// NOTE: Requires LavaLyrics plugin.
+pub struct LyricsFound {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "lyrics_found_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The lyrics of the track.
+    pub lyrics: lyrics::Lyrics,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when no lyrics are found for a track that started playing.
+///
+/// NOTE: Requires LavaLyrics plugin.
+pub struct LyricsNotFound {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "lyrics_not_found_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a line of timed lyrics is reached.
+///
+/// NOTE: Requires LavaLyrics plugin.
+pub struct LyricsLine {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "lyrics_line_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The index of the line in the lines of the lyrics.
+    pub line_index: usize,
+    /// The line that was reached.
+    pub line: lyrics::LyricsLine,
+    /// Whether the line was skipped over, like when seeking past it.
+    pub skipped: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when the SponsorBlock segments of a track that started playing are loaded.
+///
+/// NOTE: Requires SponsorBlock plugin.
+pub struct SegmentsLoaded {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "segments_loaded_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The segments of the track in the configured categories.
+    pub segments: Vec<sponsorblock::Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a SponsorBlock segment is skipped.
+///
+/// NOTE: Requires SponsorBlock plugin.
+pub struct SegmentSkipped {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "segment_skipped_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The segment that was skipped.
+    pub segment: sponsorblock::Segment,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when the chapters of a track that started playing are loaded.
+///
+/// NOTE: Requires SponsorBlock plugin.
+pub struct ChaptersLoaded {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "chapters_loaded_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The chapters of the track.
+    pub chapters: Vec<sponsorblock::Chapter>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a chapter of the playing track starts.
+///
+/// NOTE: Requires SponsorBlock plugin.
+pub struct ChapterStarted {
+    #[serde(default = "event_op")]
+    pub op: String,
+    #[serde(rename = "type", default = "chapter_started_type")]
+    pub event_type: String,
+    #[serde(deserialize_with = "deserialize_number_from_string")]
+    pub guild_id: GuildId,
+    /// The chapter that started.
+    pub chapter: sponsorblock::Chapter,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a player gets moved to another node.
+pub struct PlayerMigrated {
+    pub guild_id: GuildId,
+    /// The ID of the node the player was on.
+    pub old_node_id: usize,
+    /// The ID of the node the player is now on.
+    pub new_node_id: usize,
+    /// Why the player was moved.
+    pub reason: MigrationReason,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// The reason a player was moved to another node.
+pub enum MigrationReason {
+    /// The connection to the node was lost and could not be recovered.
+    NodeLost,
+    /// The player was moved with `move_player`.
+    Requested,
+    /// The node was removed with `remove_node`, draining its players.
+    NodeRemoved,
+    /// The Discord voice server of the guild changed region, and `NodeDistributionStrategy::ByRegion`
+    /// selected another node for it.
+    RegionChanged,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched after every attempt to reconnect to a node, and when giving up on it.
+pub struct NodeReconnect {
+    /// The ID of the node.
+    pub node_id: usize,
+    /// The attempt number, starting at 1.
+    ///
+    /// When giving up, this is the amount of attempts that were made.
+    pub attempt: u32,
+    /// The outcome of the attempt.
+    pub status: ReconnectStatus,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// The outcome of a reconnect attempt.
+pub enum ReconnectStatus {
+    /// The node is connected again.
+    Connected,
+    /// The attempt failed with this error.
+    Failed(String),
+    /// The maximum amount of attempts was reached, and no more will be made.
+    GaveUp,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a message received from Lavalink could not be parsed.
+pub struct InvalidMessage {
+    /// The message as it was received.
+    pub raw: String,
+    /// Why it could not be parsed.
+    pub error: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a player context finishes a track and its queue is empty.
+pub struct QueueEnded {
+    pub guild_id: GuildId,
+    /// The track that played last.
+    pub track: track::TrackData,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when tracks are added to the queue of a player context.
+///
+/// Tracks put back in the queue by the player itself, like when repeating, don't dispatch it.
+pub struct TrackQueued {
+    pub guild_id: GuildId,
+    /// The position of the first track in the queue.
+    pub index: usize,
+    /// The tracks that were added, in the order they are in the queue.
+    pub tracks: Vec<track::TrackData>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a player is created on a guild that didn't have one.
+pub struct PlayerCreated {
+    pub guild_id: GuildId,
+    /// The ID of the node the player was created on.
+    pub node_id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when a player is deleted with `delete_player`.
+pub struct PlayerDestroyed {
+    pub guild_id: GuildId,
+    /// The ID of the node the player was on.
+    pub node_id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when the websocket connection to a node is established, including reconnects.
+///
+/// The node is only usable once Lavalink sends the `ready` event.
+pub struct NodeConnected {
+    /// The ID of the node.
+    pub node_id: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// Dispatched when the websocket connection to a node closes, or the node is removed.
+pub struct NodeDisconnected {
+    /// The ID of the node.
+    pub node_id: usize,
+    /// Whether the node was removed with `remove_node`, in which case it won't reconnect.
+    pub removed: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+#[allow(clippy::large_enum_variant)]
+/// A message received from the Lavalink websocket, selected by its `op` field.
+///
+/// Unknown ops and event types are not an error, but messages without them, or with a known
+/// one and an invalid payload, are.
+pub enum IncomingMessage {
+    Ready(Ready),
+    PlayerUpdate(PlayerUpdate),
+    Stats(Stats),
+    Event(Event),
+    /// A message with an op this library doesn't know about.
+    #[serde(untagged, deserialize_with = "deserialize_unknown_message")]
+    Unknown(serde_json::Value),
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(tag = "type")]
+/// A message with the `event` op, selected by its `type` field.
+pub enum Event {
+    #[serde(rename = "TrackStartEvent")]
+    TrackStart(TrackStart),
+    #[serde(rename = "TrackEndEvent")]
+    TrackEnd(TrackEnd),
+    #[serde(rename = "TrackExceptionEvent")]
+    TrackException(TrackException),
+    #[serde(rename = "TrackStuckEvent")]
+    TrackStuck(TrackStuck),
+    #[serde(rename = "WebSocketClosedEvent")]
+    WebSocketClosed(WebSocketClosed),
+    #[serde(rename = "LyricsFoundEvent")]
+    LyricsFound(LyricsFound),
+    #[serde(rename = "LyricsNotFoundEvent")]
+    LyricsNotFound(LyricsNotFound),
+    #[serde(rename = "LyricsLineEvent")]
+    LyricsLine(LyricsLine),
+    #[serde(rename = "SegmentsLoaded")]
+    SegmentsLoaded(SegmentsLoaded),
+    #[serde(rename = "SegmentSkipped")]
+    SegmentSkipped(SegmentSkipped),
+    #[serde(rename = "ChaptersLoaded")]
+    ChaptersLoaded(ChaptersLoaded),
+    #[serde(rename = "ChapterStarted")]
+    ChapterStarted(ChapterStarted),
+    /// An event this library doesn't know about, like the ones sent by plugins.
+    ///
+    /// The `op` field is not part of it.
+    #[serde(untagged, deserialize_with = "deserialize_unknown_event")]
+    Unknown(serde_json::Value),
+}
+
+/// Accept a message as unknown only if its op is one this library doesn't handle, so messages
+/// with a known op and an invalid payload are still an error.
+fn deserialize_unknown_message<'de, D>(deserializer: D) -> Result<serde_json::Value, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+
+    match value.get("op").and_then(|x| x.as_str()) {
+        Some("ready" | "playerUpdate" | "stats" | "event") => {
+            Err(serde::de::Error::custom("invalid payload for a known op"))
+        }
+        Some(_) => Ok(value),
+        None => Err(serde::de::Error::custom("`op` is missing or not a string")),
+    }
+}
+
+/// Accept an event as unknown only if its type is one this library doesn't handle.
+fn deserialize_unknown_event<'de, D>(deserializer: D) -> Result<serde_json::Value, D::Error>
+where
+    D: serde::Deserializer<'de>,
+{
+    let value = serde_json::Value::deserialize(deserializer)?;
+
+    match value.get("type").and_then(|x| x.as_str()) {
+        Some(
+            "TrackStartEvent"
+            | "TrackEndEvent"
+            | "TrackExceptionEvent"
+            | "TrackStuckEvent"
+            | "WebSocketClosedEvent"
+            | "LyricsFoundEvent"
+            | "LyricsNotFoundEvent"
+            | "LyricsLineEvent"
+            | "SegmentsLoaded"
+            | "SegmentSkipped"
+            | "ChaptersLoaded"
+            | "ChapterStarted",
+        ) => Err(serde::de::Error::custom(
+            "invalid payload for a known event type",
+        )),
+        Some(_) => Ok(value),
+        None => Err(serde::de::Error::custom(
+            "`type` is missing or not a string",
+        )),
+    }
+}
+
+impl IncomingMessage {
+    /// The guild the message is about, if any.
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            IncomingMessage::PlayerUpdate(x) => Some(x.guild_id),
+            IncomingMessage::Event(x) => x.guild_id(),
+            IncomingMessage::Ready(_) | IncomingMessage::Stats(_) | IncomingMessage::Unknown(_) => {
+                None
+            }
+        }
+    }
+}
+
+impl Event {
+    /// The guild the event is about, if known.
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            Event::TrackStart(x) => Some(x.guild_id),
+            Event::TrackEnd(x) => Some(x.guild_id),
+            Event::TrackException(x) => Some(x.guild_id),
+            Event::TrackStuck(x) => Some(x.guild_id),
+            Event::WebSocketClosed(x) => Some(x.guild_id),
+            Event::LyricsFound(x) => Some(x.guild_id),
+            Event::LyricsNotFound(x) => Some(x.guild_id),
+            Event::LyricsLine(x) => Some(x.guild_id),
+            Event::SegmentsLoaded(x) => Some(x.guild_id),
+            Event::SegmentSkipped(x) => Some(x.guild_id),
+            Event::ChaptersLoaded(x) => Some(x.guild_id),
+            Event::ChapterStarted(x) => Some(x.guild_id),
+            Event::Unknown(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Any event dispatched by the client, as received by `LavalinkClient::subscribe`.
+pub enum LavalinkEvent {
+    Ready(Ready),
+    PlayerUpdate(PlayerUpdate),
+    Stats(Stats),
+    TrackStart(TrackStart),
+    TrackEnd(TrackEnd),
+    TrackException(TrackException),
+    TrackStuck(TrackStuck),
+    WebSocketClosed(WebSocketClosed),
+    LyricsFound(LyricsFound),
+    LyricsNotFound(LyricsNotFound),
+    LyricsLine(LyricsLine),
+    SegmentsLoaded(SegmentsLoaded),
+    SegmentSkipped(SegmentSkipped),
+    ChaptersLoaded(ChaptersLoaded),
+    ChapterStarted(ChapterStarted),
+    PlayerMigrated(PlayerMigrated),
+    NodeReconnect(NodeReconnect),
+    InvalidMessage(InvalidMessage),
+    QueueEnded(QueueEnded),
+    TrackQueued(TrackQueued),
+    PlayerCreated(PlayerCreated),
+    PlayerDestroyed(PlayerDestroyed),
+    NodeConnected(NodeConnected),
+    NodeDisconnected(NodeDisconnected),
+}
+
+impl LavalinkEvent {
+    /// The guild this event is related to, if any.
+    pub fn guild_id(&self) -> Option<GuildId> {
+        match self {
+            LavalinkEvent::PlayerUpdate(x) => Some(x.guild_id),
+            LavalinkEvent::TrackStart(x) => Some(x.guild_id),
+            LavalinkEvent::TrackEnd(x) => Some(x.guild_id),
+            LavalinkEvent::TrackException(x) => Some(x.guild_id),
+            LavalinkEvent::TrackStuck(x) => Some(x.guild_id),
+            LavalinkEvent::WebSocketClosed(x) => Some(x.guild_id),
+            LavalinkEvent::LyricsFound(x) => Some(x.guild_id),
+            LavalinkEvent::LyricsNotFound(x) => Some(x.guild_id),
+            LavalinkEvent::LyricsLine(x) => Some(x.guild_id),
+            LavalinkEvent::SegmentsLoaded(x) => Some(x.guild_id),
+            LavalinkEvent::SegmentSkipped(x) => Some(x.guild_id),
+            LavalinkEvent::ChaptersLoaded(x) => Some(x.guild_id),
+            LavalinkEvent::ChapterStarted(x) => Some(x.guild_id),
+            LavalinkEvent::PlayerMigrated(x) => Some(x.guild_id),
+            LavalinkEvent::QueueEnded(x) => Some(x.guild_id),
+            LavalinkEvent::TrackQueued(x) => Some(x.guild_id),
+            LavalinkEvent::PlayerCreated(x) => Some(x.guild_id),
+            LavalinkEvent::PlayerDestroyed(x) => Some(x.guild_id),
+            LavalinkEvent::Ready(_)
+            | LavalinkEvent::Stats(_)
+            | LavalinkEvent::NodeReconnect(_)
+            | LavalinkEvent::InvalidMessage(_)
+            | LavalinkEvent::NodeConnected(_)
+            | LavalinkEvent::NodeDisconnected(_) => None,
+        }
+    }
+}
+
+macro_rules! impl_from_event {
+    ($($variant:ident),* $(,)?) => {
+        $(
+            impl From<$variant> for LavalinkEvent {
+                fn from(event: $variant) -> Self {
+                    LavalinkEvent::$variant(event)
+                }
+            }
+        )*
+    };
+}
+
+impl_from_event!(
+    Ready,
+    PlayerUpdate,
+    Stats,
+    TrackStart,
+    TrackEnd,
+    TrackException,
+    TrackStuck,
+    WebSocketClosed,
+    LyricsFound,
+    LyricsNotFound,
+    LyricsLine,
+    SegmentsLoaded,
+    SegmentSkipped,
+    ChaptersLoaded,
+    ChapterStarted,
+    PlayerMigrated,
+    NodeReconnect,
+    InvalidMessage,
+    QueueEnded,
+    TrackQueued,
+    PlayerCreated,
+    PlayerDestroyed,
+    NodeConnected,
+    NodeDisconnected,
+);
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TRACK: &str = r#"{"encoded":"QAAAjQIAJVJpY2sgQXN0bGV5IC0gTmV2ZXIgR29ubmEgR2l2ZSBZb3UgVXAADlJpY2tBc3RsZXlWRVZPAAAAAAADPCAAC2RRdzR3OVdnWGNRAAEAK2h0dHBzOi8vd3d3LnlvdXR1YmUuY29tL3dhdGNoP3Y9ZFF3NHc5V2dYY1EAB3lvdXR1YmUAAAAAAAAAAA==","info":{"identifier":"dQw4w9WgXcQ","isSeekable":true,"author":"RickAstleyVEVO","length":212000,"isStream":false,"position":0,"title":"Rick Astley - Never Gonna Give You Up","uri":"https://www.youtube.com/watch?v=dQw4w9WgXcQ","artworkUrl":null,"isrc":null,"sourceName":"youtube"},"pluginInfo":{},"userData":{}}"#;
+
+    fn valid_frames() -> Vec<String> {
+        vec![
+            r#"{"op":"ready","resumed":false,"sessionId":"la3kfsdf5eafe848"}"#.to_string(),
+            r#"{"op":"playerUpdate","guildId":"817327181659111454","state":{"time":1500467109,"position":60000,"connected":true,"ping":50}}"#.to_string(),
+            r#"{"op":"stats","players":1,"playingPlayers":1,"uptime":123456789,"memory":{"free":123456789,"used":123456789,"allocated":123456789,"reservable":123456789},"cpu":{"cores":4,"systemLoad":0.5,"lavalinkLoad":0.5},"frameStats":{"sent":6000,"nulled":10,"deficit":-3010}}"#.to_string(),
+            format!(r#"{{"op":"event","type":"TrackStartEvent","guildId":"817327181659111454","track":{}}}"#, TRACK),
+            format!(r#"{{"op":"event","type":"TrackEndEvent","guildId":"817327181659111454","track":{},"reason":"finished"}}"#, TRACK),
+            format!(r#"{{"op":"event","type":"TrackExceptionEvent","guildId":"817327181659111454","track":{},"exception":{{"message":"...","severity":"common","cause":"..."}}}}"#, TRACK),
+            format!(r#"{{"op":"event","type":"TrackStuckEvent","guildId":"817327181659111454","track":{},"thresholdMs":123456789}}"#, TRACK),
+            r#"{"op":"event","type":"WebSocketClosedEvent","guildId":"817327181659111454","code":4006,"reason":"Your session is no longer valid.","byRemote":true}"#.to_string(),
+            r#"{"op":"event","type":"LyricsFoundEvent","guildId":"817327181659111454","lyrics":{"sourceName":"youtube","provider":"Musixmatch","text":null,"lines":[{"timestamp":18000,"duration":3500,"line":"We're no strangers to love","plugin":{}}],"plugin":{}}}"#.to_string(),
+            r#"{"op":"event","type":"LyricsNotFoundEvent","guildId":"817327181659111454"}"#.to_string(),
+            r#"{"op":"event","type":"LyricsLineEvent","guildId":"817327181659111454","lineIndex":0,"line":{"timestamp":18000,"duration":null,"line":"We're no strangers to love","plugin":{}},"skipped":false}"#.to_string(),
+            r#"{"op":"event","type":"SegmentsLoaded","guildId":"817327181659111454","segments":[{"category":"intro","start":0,"end":12000},{"category":"selfpromo","start":200000,"end":212000}]}"#.to_string(),
+            r#"{"op":"event","type":"SegmentSkipped","guildId":"817327181659111454","segment":{"category":"music_offtopic","start":0,"end":12000}}"#.to_string(),
+            r#"{"op":"event","type":"ChaptersLoaded","guildId":"817327181659111454","chapters":[{"name":"Intro","start":0,"end":12000,"duration":12000}]}"#.to_string(),
+            r#"{"op":"event","type":"ChapterStarted","guildId":"817327181659111454","chapter":{"name":"Intro","start":0,"end":12000,"duration":12000}}"#.to_string(),
+        ]
+    }
+
+    #[test]
+    fn parses_valid_frames() {
+        let parsed = valid_frames()
+            .iter()
+            .map(|x| serde_json::from_str::<IncomingMessage>(x).unwrap())
+            .collect::<Vec<_>>();
+
+        assert!(
+            matches!(parsed[0], IncomingMessage::Ready(ref x) if x.session_id == "la3kfsdf5eafe848")
+        );
+        assert!(
+            matches!(parsed[1], IncomingMessage::PlayerUpdate(ref x) if x.guild_id.0 == 817327181659111454)
+        );
+        assert!(matches!(parsed[2], IncomingMessage::Stats(ref x) if x.frame_stats.is_some()));
+        assert!(
+            matches!(parsed[3], IncomingMessage::Event(Event::TrackStart(ref x)) if x.op == "event" && x.event_type == "TrackStartEvent")
+        );
+        assert!(
+            matches!(parsed[4], IncomingMessage::Event(Event::TrackEnd(ref x)) if x.reason == TrackEndReason::Finished)
+        );
+        assert!(matches!(
+            parsed[5],
+            IncomingMessage::Event(Event::TrackException(_))
+        ));
+        assert!(matches!(
+            parsed[6],
+            IncomingMessage::Event(Event::TrackStuck(_))
+        ));
+        assert!(
+            matches!(parsed[7], IncomingMessage::Event(Event::WebSocketClosed(ref x)) if x.code == 4006)
+        );
+        assert!(
+            matches!(parsed[8], IncomingMessage::Event(Event::LyricsFound(ref x)) if x.lyrics.is_timed())
+        );
+        assert!(matches!(
+            parsed[9],
+            IncomingMessage::Event(Event::LyricsNotFound(_))
+        ));
+        assert!(
+            matches!(parsed[10], IncomingMessage::Event(Event::LyricsLine(ref x)) if x.line.duration.is_none())
+        );
+        assert!(
+            matches!(parsed[11], IncomingMessage::Event(Event::SegmentsLoaded(ref x)) if x.segments[1].category == sponsorblock::SegmentCategory::SelfPromo)
+        );
+        assert!(
+            matches!(parsed[12], IncomingMessage::Event(Event::SegmentSkipped(ref x)) if x.segment.category == sponsorblock::SegmentCategory::MusicOfftopic)
+        );
+        assert!(
+            matches!(parsed[13], IncomingMessage::Event(Event::ChaptersLoaded(ref x)) if x.chapters.len() == 1)
+        );
+        assert!(
+            matches!(parsed[14], IncomingMessage::Event(Event::ChapterStarted(ref x)) if x.chapter.name == "Intro")
+        );
+    }
+
+    #[test]
+    fn unknown_ops_and_events_fall_back() {
+        let message = serde_json::from_str::<IncomingMessage>(r#"{"op":"futureOp","data":1}"#);
+        assert!(matches!(message, Ok(IncomingMessage::Unknown(_))));
+
+        let message = serde_json::from_str::<IncomingMessage>(
+            r#"{"op":"event","type":"FutureEvent","guildId":"1","data":[]}"#,
+        );
+        assert!(matches!(
+            message,
+            Ok(IncomingMessage::Event(Event::Unknown(_)))
+        ));
+    }
+
+    #[test]
+    fn unknown_messages_keep_their_payload() {
+        let message = serde_json::from_str::<IncomingMessage>(
+            r#"{"op":"event","type":"PluginEvent","guildId":"1","data":{"votes":[1,2]}}"#,
+        )
+        .unwrap();
+        let IncomingMessage::Event(Event::Unknown(event)) = message else {
+            panic!("parsed as a known event: {:?}", message);
+        };
+        assert_eq!(event["type"], "PluginEvent");
+        assert_eq!(event["guildId"], "1");
+        assert_eq!(event["data"]["votes"], serde_json::json!([1, 2]));
+
+        let message =
+            serde_json::from_str::<IncomingMessage>(r#"{"op":"futureOp","data":1}"#).unwrap();
+        assert_eq!(
+            message,
+            IncomingMessage::Unknown(serde_json::json!({"op": "futureOp", "data": 1}))
+        );
+    }
+
+    #[test]
+    fn rejects_malformed_frames() {
+        let corpus = [
+            "",
+            " ",
+            "null",
+            "true",
+            "42",
+            r#""ready""#,
+            "[]",
+            r#"[{"op":"ready"}]"#,
+            "{}",
+            r#"{"op":null}"#,
+            r#"{"op":1}"#,
+            r#"{"op":{"nested":"ready"}}"#,
+            r#"{"op":"ready"}"#,
+            r#"{"op":"ready","sessionId":1,"resumed":false}"#,
+            r#"{"op":"ready","sessionId":"abc","resumed":"no"}"#,
+            r#"{"op":"playerUpdate","guildId":"not a number","state":{"time":0,"position":0,"connected":false,"ping":-1}}"#,
+            r#"{"op":"playerUpdate","guildId":"1"}"#,
+            r#"{"op":"stats","players":-1}"#,
+            r#"{"op":"event"}"#,
+            r#"{"op":"event","type":7}"#,
+            r#"{"op":"event","type":"TrackStartEvent"}"#,
+            r#"{"op":"event","type":"TrackStartEvent","guildId":"1","track":null}"#,
+            r#"{"op":"event","type":"TrackEndEvent","guildId":"1","track":{},"reason":"exploded"}"#,
+            r#"{"op":"event","type":"WebSocketClosedEvent","guildId":"1","code":99999999,"reason":"","byRemote":true}"#,
+            r#"{"op":"event","type":"LyricsFoundEvent","guildId":"1","lyrics":null}"#,
+            r#"{"op":"event","type":"SegmentSkipped","guildId":"1","segment":{"category":"everything","start":0,"end":1}}"#,
+            r#"{"op":"event","type":"LyricsLineEvent","guildId":"1","lineIndex":-1,"line":{},"skipped":false}"#,
+            "{\"op\":\"ready\",\"sessionId\":\"\u{0}\"",
+            r#"{"op":"ready","resumed":false,"sessionId":"abc"}}"#,
+        ];
+
+        for frame in corpus {
+            assert!(
+                serde_json::from_str::<IncomingMessage>(frame).is_err(),
+                "parsed malformed frame: {}",
+                frame
+            );
+        }
+    }
+
+    #[test]
+    fn survives_truncated_and_mutated_frames() {
+        for frame in valid_frames() {
+            for end in 0..frame.len() {
+                if frame.is_char_boundary(end) {
+                    let _ = serde_json::from_str::<IncomingMessage>(&frame[..end]);
+                }
+            }
+
+            let value = serde_json::from_str::<serde_json::Value>(&frame).unwrap();
+            let object = value.as_object().unwrap();
+
+            for key in object.keys() {
+                let mut without = object.clone();
+                without.remove(key);
+                let _ =
+                    serde_json::from_value::<IncomingMessage>(serde_json::Value::Object(without));
+
+                for replacement in [
+                    serde_json::Value::Null,
+                    serde_json::json!(-1),
+                    serde_json::json!(""),
+                    serde_json::json!([]),
+                    serde_json::json!({}),
+                ] {
+                    let mut mutated = object.clone();
+                    mutated.insert(key.clone(), replacement);
+                    let _ = serde_json::from_value::<IncomingMessage>(serde_json::Value::Object(
+                        mutated,
+                    ));
+                }
+            }
+        }
+    }
+}
@@ -134,3 +134,124 @@ pub struct Version {
     pub pre_release: Option<String>,
     pub build: Option<String>,
 }
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "class", content = "details")]
+/// The status of the route planner, which picks the IP address requests to the sources are
+/// sent from.
+pub enum RoutePlannerStatus {
+    /// Switches to the next IP address once the current one gets banned.
+    #[serde(rename = "RotatingIpRoutePlanner")]
+    RotatingIp(RotatingIpDetails),
+    /// Switches to the next IP address with every request.
+    #[serde(rename = "NanoIpRoutePlanner")]
+    NanoIp(NanoIpDetails),
+    /// Switches to the next IP address with every request, and to the next /64 block once the
+    /// current one gets banned.
+    #[serde(rename = "RotatingNanoIpRoutePlanner")]
+    RotatingNanoIp(RotatingNanoIpDetails),
+    /// Picks a random IP address for every request.
+    #[serde(rename = "BalancingIpRoutePlanner")]
+    BalancingIp(BalancingIpDetails),
+}
+
+impl RoutePlannerStatus {
+    /// The IP block the addresses are picked from.
+    pub fn ip_block(&self) -> &IpBlock {
+        match self {
+            Self::RotatingIp(x) => &x.ip_block,
+            Self::NanoIp(x) => &x.ip_block,
+            Self::RotatingNanoIp(x) => &x.ip_block,
+            Self::BalancingIp(x) => &x.ip_block,
+        }
+    }
+
+    /// The addresses that are currently banned.
+    pub fn failing_addresses(&self) -> &[FailingAddress] {
+        match self {
+            Self::RotatingIp(x) => &x.failing_addresses,
+            Self::NanoIp(x) => &x.failing_addresses,
+            Self::RotatingNanoIp(x) => &x.failing_addresses,
+            Self::BalancingIp(x) => &x.failing_addresses,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+pub struct RotatingIpDetails {
+    pub ip_block: IpBlock,
+    pub failing_addresses: Vec<FailingAddress>,
+    /// The number of rotations.
+    pub rotate_index: String,
+    /// The current offset in the block.
+    pub ip_index: String,
+    /// The current address being used.
+    pub current_address: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+pub struct NanoIpDetails {
+    pub ip_block: IpBlock,
+    pub failing_addresses: Vec<FailingAddress>,
+    /// The current offset in the IP block.
+    pub current_address_index: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+pub struct RotatingNanoIpDetails {
+    pub ip_block: IpBlock,
+    pub failing_addresses: Vec<FailingAddress>,
+    /// The current offset in the IP block.
+    pub block_index: String,
+    /// The current offset in the /64 block.
+    pub current_address_index: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+pub struct BalancingIpDetails {
+    pub ip_block: IpBlock,
+    pub failing_addresses: Vec<FailingAddress>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+pub struct IpBlock {
+    /// The type of the addresses in the block.
+    #[serde(rename = "type")]
+    pub kind: IpBlockType,
+    /// The amount of addresses in the block.
+    ///
+    /// It's a string because IPv6 blocks can be larger than any integer type.
+    pub size: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+pub enum IpBlockType {
+    /// An IPv4 block.
+    Inet4Address,
+    /// An IPv6 block.
+    Inet6Address,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// An address that got banned, and won't be used until it's freed.
+pub struct FailingAddress {
+    /// The address.
+    pub failing_address: String,
+    /// The millisecond unix timestamp when the address failed.
+    pub failing_timestamp: u64,
+    /// The time when the address failed, as a pretty string.
+    pub failing_time: String,
+}
@@ -0,0 +1,52 @@
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// The plugin info LavaSrc adds to the tracks it loads, from `TrackData::lavasrc_info`.
+///
+/// NOTE: Requires LavaSrc plugin.
+pub struct LavaSrcTrackInfo {
+    /// The name of the album of the track.
+    pub album_name: Option<String>,
+    /// The url of the album of the track.
+    pub album_url: Option<String>,
+    /// The url of the artist of the track.
+    pub artist_url: Option<String>,
+    /// The artwork url of the artist of the track.
+    pub artist_artwork_url: Option<String>,
+    /// The url of a preview of the track.
+    pub preview_url: Option<String>,
+    /// Whether the track is only a preview, because the full track is not available.
+    pub is_preview: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// The plugin info LavaSrc adds to the playlists it loads, from `PlaylistData::lavasrc_info`.
+///
+/// NOTE: Requires LavaSrc plugin.
+pub struct LavaSrcPlaylistInfo {
+    /// What the playlist is made from.
+    #[serde(rename = "type")]
+    pub kind: Option<LavaSrcPlaylistType>,
+    /// The url of the playlist.
+    pub url: Option<String>,
+    /// The artwork url of the playlist.
+    pub artwork_url: Option<String>,
+    /// The author of the playlist.
+    pub author: Option<String>,
+    /// The amount of tracks of the playlist, which can be more than the tracks that were loaded.
+    pub total_tracks: Option<u32>,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// What a playlist loaded by LavaSrc is made from.
+pub enum LavaSrcPlaylistType {
+    Album,
+    Playlist,
+    /// The top tracks of an artist.
+    Artist,
+    Recommendations,
+}
@@ -0,0 +1,83 @@
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// The lyrics of a track.
+///
+/// NOTE: Requires LavaLyrics plugin.
+pub struct Lyrics {
+    /// The name of the source the lyrics were loaded from, like "youtube" or "deezer".
+    pub source_name: String,
+    /// The provider of the lyrics, as reported by the source.
+    pub provider: String,
+    /// The lyrics as plain text, if the source provides them that way.
+    pub text: Option<String>,
+    /// The timed lines of the lyrics, empty if the source has no timing information.
+    #[serde(default)]
+    pub lines: Vec<LyricsLine>,
+    /// Addition lyrics information provided by plugins.
+    pub plugin: Option<serde_json::Value>,
+}
+
+impl Lyrics {
+    /// Whether the lyrics have timing information, and can follow the track as it plays.
+    pub fn is_timed(&self) -> bool {
+        !self.lines.is_empty()
+    }
+
+    /// The index of the line sung at this position of the track, in milliseconds.
+    ///
+    /// None if the lyrics are not timed, or the position is before the first line.
+    pub fn line_at(&self, position: u64) -> Option<usize> {
+        self.lines
+            .partition_point(|x| x.timestamp <= position)
+            .checked_sub(1)
+    }
+
+    /// The lyrics as plain text, made from the lines if the source has no plain text.
+    pub fn to_text(&self) -> String {
+        match &self.text {
+            Some(text) => text.clone(),
+            None => self
+                .lines
+                .iter()
+                .map(|x| x.line.as_str())
+                .collect::<Vec<_>>()
+                .join("\n"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// A single line of timed lyrics.
+pub struct LyricsLine {
+    /// When the line starts, in milliseconds from the start of the track.
+    pub timestamp: u64,
+    /// How long the line lasts in milliseconds, if the source provides it.
+    pub duration: Option<u64>,
+    /// The text of the line.
+    pub line: String,
+    /// Addition line information provided by plugins.
+    pub plugin: Option<serde_json::Value>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_lines_by_position() {
+        let lyrics = serde_json::from_str::<Lyrics>(
+            r#"{"sourceName":"deezer","provider":"LyricFind","text":null,"lines":[{"timestamp":1000,"duration":2000,"line":"first","plugin":{}},{"timestamp":3000,"duration":null,"line":"second","plugin":{}}],"plugin":{}}"#,
+        )
+        .unwrap();
+
+        assert!(lyrics.is_timed());
+        assert_eq!(lyrics.line_at(0), None);
+        assert_eq!(lyrics.line_at(1000), Some(0));
+        assert_eq!(lyrics.line_at(2999), Some(0));
+        assert_eq!(lyrics.line_at(60000), Some(1));
+        assert_eq!(lyrics.to_text(), "first\nsecond");
+    }
+}
@@ -11,10 +11,16 @@ pub mod client;
 pub mod events;
 /// Models related to the lavalink REST API.
 pub mod http;
+/// Models related to the LavaSrc plugin.
+pub mod lavasrc;
+/// Models related to lyrics.
+pub mod lyrics;
 /// Models related to the lavalink Player.
 pub mod player;
 /// Models related to search engines.
 pub mod search;
+/// Models related to the SponsorBlock plugin.
+pub mod sponsorblock;
 /// Models related to the tracks.
 pub mod track;
 
@@ -92,7 +98,10 @@ pub(crate) fn deserialize_option_number<'de, D>(deserializer: D) -> Result<Optio
 where
     D: Deserializer<'de>,
 {
-    let n = i32::deserialize(deserializer)?;
+    // Lavalink sends -1 for None, but null is accepted too so serialized values parse back.
+    let Some(n) = Option::<i32>::deserialize(deserializer)? else {
+        return Ok(None);
+    };
     Ok(match n.cmp(&-1) {
         std::cmp::Ordering::Less => return Err(de::Error::custom("integer {n} is below -1")),
         std::cmp::Ordering::Equal => None,
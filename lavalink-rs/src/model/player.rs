@@ -1,3 +1,4 @@
+use crate::error::{LavalinkError, LavalinkResult};
 use crate::model::*;
 
 #[derive(PartialEq, Debug, Clone, Serialize, Deserialize)]
@@ -59,6 +60,44 @@ pub struct ConnectionInfo {
     pub session_id: String,
 }
 
+impl Player {
+    /// Build the request that recreates this player as it is, including the current position.
+    pub(crate) fn into_update_player(self) -> http::UpdatePlayer {
+        let position = self.track.as_ref().map(|track| {
+            let mut position = self.state.position;
+
+            if !self.paused {
+                let now = std::time::SystemTime::now()
+                    .duration_since(std::time::UNIX_EPOCH)
+                    .map(|x| x.as_millis() as u64)
+                    .unwrap_or(self.state.time);
+
+                position += now.saturating_sub(self.state.time);
+            }
+
+            if track.info.is_stream {
+                position
+            } else {
+                position.min(track.info.length)
+            }
+        });
+
+        http::UpdatePlayer {
+            track: self.track.map(|track| http::UpdatePlayerTrack {
+                encoded: Some(track.encoded),
+                user_data: track.user_data,
+                ..Default::default()
+            }),
+            position,
+            volume: Some(self.volume),
+            paused: Some(self.paused),
+            filters: self.filters,
+            voice: Some(self.voice),
+            ..Default::default()
+        }
+    }
+}
+
 impl ConnectionInfo {
     pub fn fix(&mut self) {
         self.endpoint = self.endpoint.replace("wss://", "");
@@ -120,6 +159,295 @@ pub struct Filters {
     pub plugin_filters: Option<serde_json::Value>,
 }
 
+/// The amount of bands the Lavalink equalizer has.
+pub const EQUALIZER_BANDS: u8 = 15;
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy)]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// How strong the bass boost preset is.
+pub enum BassBoost {
+    Low,
+    Medium,
+    High,
+    Extreme,
+}
+
+impl BassBoost {
+    /// The gain applied to the lowest band, the following bands fade out from it.
+    pub fn gain(&self) -> f64 {
+        match self {
+            BassBoost::Low => 0.1,
+            BassBoost::Medium => 0.2,
+            BassBoost::High => 0.35,
+            BassBoost::Extreme => 0.5,
+        }
+    }
+}
+
+#[derive(PartialEq, Debug, Clone, Copy)]
+/// Commonly used combinations of filters.
+pub enum FilterPreset {
+    /// Raises the gain of the lowest equalizer bands.
+    BassBoost(BassBoost),
+    /// Speeds up the track and raises its pitch.
+    Nightcore,
+    /// Slows down the track, lowers its pitch and boosts the lows.
+    Vaporwave,
+    /// Rotates the audio around the listener.
+    EightD,
+    /// Suppresses the higher frequencies.
+    Soft,
+    /// Removes the vocals of the track.
+    Karaoke,
+}
+
+impl Equalizer {
+    /// Create an equalizer band, checking that both the band and the gain are in range.
+    pub fn new(band: u8, gain: f64) -> LavalinkResult<Equalizer> {
+        if band >= EQUALIZER_BANDS {
+            return Err(LavalinkError::InvalidFilter(format!(
+                "the equalizer band {} is not between 0 and {}",
+                band,
+                EQUALIZER_BANDS - 1
+            )));
+        }
+
+        if !(-0.25..=1.0).contains(&gain) {
+            return Err(LavalinkError::InvalidFilter(format!(
+                "the gain {} of the equalizer band {} is not between -0.25 and 1.0",
+                gain, band
+            )));
+        }
+
+        Ok(Equalizer { band, gain })
+    }
+}
+
+impl Filters {
+    /// Start building filters from scratch.
+    pub fn builder() -> FiltersBuilder {
+        FiltersBuilder::default()
+    }
+
+    /// Get the filters of a preset.
+    pub fn preset(preset: FilterPreset) -> Filters {
+        Filters::builder().preset(preset).filters
+    }
+
+    /// Overwrite the filters that are set in `other`, keeping the rest.
+    ///
+    /// Equalizer bands are merged band by band.
+    pub fn merge(&mut self, other: Filters) {
+        if let Some(bands) = other.equalizer {
+            for band in bands {
+                self.set_band(band);
+            }
+        }
+
+        macro_rules! merge {
+            ($($field:ident),* $(,)?) => {
+                $(
+                    if other.$field.is_some() {
+                        self.$field = other.$field;
+                    }
+                )*
+            };
+        }
+
+        merge!(
+            volume,
+            karaoke,
+            timescale,
+            tremolo,
+            vibrato,
+            rotation,
+            distortion,
+            channel_mix,
+            low_pass,
+            plugin_filters,
+        );
+    }
+
+    fn set_band(&mut self, band: Equalizer) {
+        let bands = self.equalizer.get_or_insert_with(Vec::new);
+
+        match bands.binary_search_by_key(&band.band, |x| x.band) {
+            Ok(idx) => bands[idx] = band,
+            Err(idx) => bands.insert(idx, band),
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+/// A builder for [`Filters`].
+///
+/// The filter setters take an `Option`-like value, passing `None` disables that filter.
+/// Invalid values are reported when calling [`FiltersBuilder::build`].
+pub struct FiltersBuilder {
+    filters: Filters,
+    error: Option<LavalinkError>,
+}
+
+impl From<Filters> for FiltersBuilder {
+    fn from(filters: Filters) -> FiltersBuilder {
+        FiltersBuilder {
+            filters,
+            error: None,
+        }
+    }
+}
+
+macro_rules! filter_setters {
+    ($($(#[$meta:meta])* $field:ident: $ty:ty),* $(,)?) => {
+        $(
+            $(#[$meta])*
+            pub fn $field(mut self, $field: impl Into<Option<$ty>>) -> Self {
+                self.filters.$field = $field.into();
+                self
+            }
+        )*
+    };
+}
+
+impl FiltersBuilder {
+    filter_setters!(
+        /// Set the karaoke filter.
+        karaoke: Karaoke,
+        /// Set the timescale filter.
+        timescale: Timescale,
+        /// Set the tremolo filter.
+        tremolo: TremoloVibrato,
+        /// Set the vibrato filter.
+        vibrato: TremoloVibrato,
+        /// Set the rotation filter.
+        rotation: Rotation,
+        /// Set the distortion filter.
+        distortion: Distortion,
+        /// Set the channel mix filter.
+        channel_mix: ChannelMix,
+        /// Set the low pass filter.
+        low_pass: LowPass,
+        /// Set the filter plugin configurations.
+        plugin_filters: serde_json::Value,
+    );
+
+    fn fail(&mut self, error: LavalinkError) {
+        self.error.get_or_insert(error);
+    }
+
+    /// Set the volume filter, from 0.0 to 5.0 where 1.0 is 100%.
+    pub fn volume(mut self, volume: impl Into<Option<f64>>) -> Self {
+        let volume = volume.into();
+
+        if let Some(volume) = volume.filter(|x| !(0.0..=5.0).contains(x)) {
+            self.fail(LavalinkError::InvalidFilter(format!(
+                "the volume {} is not between 0.0 and 5.0",
+                volume
+            )));
+        }
+
+        self.filters.volume = volume;
+        self
+    }
+
+    /// Replace the whole equalizer.
+    pub fn equalizer(mut self, bands: impl Into<Option<Vec<Equalizer>>>) -> Self {
+        self.filters.equalizer = None;
+
+        for band in bands.into().into_iter().flatten() {
+            self = self.band(band.band, band.gain);
+        }
+
+        self
+    }
+
+    /// Set the gain of a single equalizer band, keeping the other bands.
+    pub fn band(mut self, band: u8, gain: f64) -> Self {
+        match Equalizer::new(band, gain) {
+            Ok(band) => self.filters.set_band(band),
+            Err(why) => self.fail(why),
+        }
+
+        self
+    }
+
+    /// Set the gains of the equalizer bands in order, starting from band 0.
+    pub fn gains(mut self, gains: &[f64]) -> Self {
+        if gains.len() > EQUALIZER_BANDS as usize {
+            self.fail(LavalinkError::InvalidFilter(format!(
+                "{} gains were provided, but the equalizer only has {} bands",
+                gains.len(),
+                EQUALIZER_BANDS
+            )));
+        }
+
+        for (band, gain) in (0..EQUALIZER_BANDS).zip(gains) {
+            self = self.band(band, *gain);
+        }
+
+        self
+    }
+
+    /// Apply a preset on top of the current filters.
+    pub fn preset(self, preset: FilterPreset) -> Self {
+        match preset {
+            FilterPreset::BassBoost(level) => {
+                let gain = level.gain();
+
+                self.gains(&[gain, gain, gain * 0.8, gain * 0.5, gain * 0.25])
+            }
+            FilterPreset::Nightcore => self.timescale(Timescale {
+                speed: Some(1.2),
+                pitch: Some(1.2),
+                rate: Some(1.0),
+            }),
+            FilterPreset::Vaporwave => self
+                .timescale(Timescale {
+                    speed: Some(0.85),
+                    pitch: Some(0.8),
+                    rate: Some(1.0),
+                })
+                .band(0, 0.3)
+                .band(1, 0.3),
+            FilterPreset::EightD => self.rotation(Rotation {
+                rotation_hz: Some(0.2),
+            }),
+            FilterPreset::Soft => self.low_pass(LowPass {
+                smoothing: Some(20.0),
+            }),
+            FilterPreset::Karaoke => self.karaoke(Karaoke {
+                level: Some(1.0),
+                mono_level: Some(1.0),
+                filter_band: Some(220.0),
+                filter_width: Some(100.0),
+            }),
+        }
+    }
+
+    /// Overwrite the filters that are set in `other`, see [`Filters::merge`].
+    pub fn merge(mut self, other: Filters) -> Self {
+        if let Some(bands) = other.equalizer.clone() {
+            for band in bands {
+                self = self.band(band.band, band.gain);
+            }
+        }
+
+        self.filters.merge(Filters {
+            equalizer: None,
+            ..other
+        });
+        self
+    }
+
+    /// Get the filters, or the first invalid value that was provided.
+    pub fn build(self) -> LavalinkResult<Filters> {
+        match self.error {
+            Some(why) => Err(why),
+            None => Ok(self.filters),
+        }
+    }
+}
+
 #[derive(PartialEq, PartialOrd, Debug, Clone, Serialize, Deserialize, Default)]
 #[serde(rename_all = "camelCase")]
 #[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
@@ -258,3 +586,94 @@ pub struct TremoloVibrato {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub depth: Option<f64>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn validates_equalizer_bands() {
+        assert!(Equalizer::new(14, 1.0).is_ok());
+        assert!(Equalizer::new(15, 0.0).is_err());
+        assert!(Equalizer::new(0, -0.5).is_err());
+        assert!(Equalizer::new(0, f64::NAN).is_err());
+
+        assert!(Filters::builder().gains(&[0.1; 16]).build().is_err());
+        assert!(Filters::builder().volume(6.0).build().is_err());
+
+        let filters = Filters::builder()
+            .band(3, 0.2)
+            .band(1, 0.1)
+            .band(3, -0.1)
+            .build()
+            .unwrap();
+
+        assert_eq!(
+            filters.equalizer,
+            Some(vec![
+                Equalizer { band: 1, gain: 0.1 },
+                Equalizer {
+                    band: 3,
+                    gain: -0.1
+                },
+            ])
+        );
+    }
+
+    #[test]
+    fn round_trips_the_ping() {
+        let state =
+            serde_json::from_str::<State>(r#"{"time":0,"position":0,"connected":false,"ping":-1}"#)
+                .unwrap();
+        assert_eq!(state.ping, None);
+
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(value["ping"], serde_json::Value::Null);
+        assert_eq!(serde_json::from_value::<State>(value).unwrap(), state);
+
+        let state = State {
+            ping: Some(50),
+            ..state
+        };
+        let value = serde_json::to_value(&state).unwrap();
+        assert_eq!(serde_json::from_value::<State>(value).unwrap(), state);
+    }
+
+    #[test]
+    fn merges_filters() {
+        let mut filters = Filters::builder()
+            .preset(FilterPreset::Nightcore)
+            .preset(FilterPreset::BassBoost(BassBoost::Low))
+            .build()
+            .unwrap();
+
+        filters.merge(
+            Filters::builder()
+                .band(0, 0.5)
+                .band(10, 0.2)
+                .build()
+                .unwrap(),
+        );
+
+        assert_eq!(
+            filters.timescale,
+            Filters::preset(FilterPreset::Nightcore).timescale
+        );
+
+        let bands = filters.equalizer.as_ref().unwrap();
+        assert_eq!(bands.len(), 6);
+        assert_eq!(bands[0].gain, 0.5);
+        assert_eq!(bands[1].gain, 0.1);
+        assert_eq!(bands[5].band, 10);
+
+        let filters = FiltersBuilder::from(filters)
+            .timescale(None)
+            .preset(FilterPreset::EightD)
+            .build()
+            .unwrap();
+
+        assert!(filters.timescale.is_none());
+        assert!(filters.rotation.is_some());
+        assert_eq!(filters.equalizer.unwrap().len(), 6);
+    }
+}
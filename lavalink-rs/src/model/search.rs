@@ -1,6 +1,7 @@
 #![allow(clippy::large_enum_variant)]
 
 use crate::error::LavalinkResult;
+use crate::model::track::{PlaylistData, TrackData};
 
 /// Search engines supported by Lavalink and LavaSrc.
 pub enum SearchEngines {
@@ -66,6 +67,111 @@ impl SearchEngines {
     }
 }
 
+/// Search engines supported by the LavaSearch plugin, for `load_search()`.
+///
+/// NOTE: Requires LavaSearch and LavaSrc plugins.
+pub enum LavaSearchEngines {
+    Spotify,
+    AppleMusic,
+    Deezer,
+    YandexMusic,
+}
+
+impl std::fmt::Display for LavaSearchEngines {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use LavaSearchEngines::*;
+        match self {
+            Spotify => f.write_str("spsearch"),
+            AppleMusic => f.write_str("amsearch"),
+            Deezer => f.write_str("dzsearch"),
+            YandexMusic => f.write_str("ymsearch"),
+        }
+    }
+}
+
+impl LavaSearchEngines {
+    /// Create a String you can pipe to `load_search()` to get the search results.
+    ///
+    /// Example:
+    /// ```rust,ignore
+    /// let query = LavaSearchEngines::Spotify.to_query("Ne Obliviscaris");
+    /// lavalink_client.load_search(guild_id, &query, &[SearchType::Album]).await?;
+    /// ```
+    pub fn to_query(&self, base_query: &str) -> String {
+        format!("{}:{}", self, base_query)
+    }
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// The kinds of results LavaSearch can return.
+pub enum SearchType {
+    Track,
+    Album,
+    Artist,
+    Playlist,
+    Text,
+}
+
+impl std::fmt::Display for SearchType {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        use SearchType::*;
+        match self {
+            Track => f.write_str("track"),
+            Album => f.write_str("album"),
+            Artist => f.write_str("artist"),
+            Playlist => f.write_str("playlist"),
+            Text => f.write_str("text"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// The results of a LavaSearch search.
+///
+/// Albums, artists and playlists are returned like playlists, their tracks being the top tracks
+/// when the source provides them.
+pub struct SearchResult {
+    #[serde(default)]
+    pub tracks: Vec<TrackData>,
+    #[serde(default)]
+    pub albums: Vec<PlaylistData>,
+    #[serde(default)]
+    pub artists: Vec<PlaylistData>,
+    #[serde(default)]
+    pub playlists: Vec<PlaylistData>,
+    /// Text suggestions for the query, like corrected spellings.
+    #[serde(default)]
+    pub texts: Vec<SearchText>,
+    /// Addition result information provided by plugins.
+    pub plugin: Option<serde_json::Value>,
+}
+
+impl SearchResult {
+    /// Whether nothing was found.
+    pub fn is_empty(&self) -> bool {
+        self.tracks.is_empty()
+            && self.albums.is_empty()
+            && self.artists.is_empty()
+            && self.playlists.is_empty()
+            && self.texts.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// A text suggestion returned by LavaSearch.
+pub struct SearchText {
+    /// The suggested text.
+    pub text: String,
+    /// Addition text information provided by plugins.
+    pub plugin: Option<serde_json::Value>,
+}
+
 /// Any of the seed fields must have a value.
 ///
 /// Spotify documentation can be found [here](https://developer.spotify.com/documentation/web-api/reference/get-recommendations)
@@ -0,0 +1,62 @@
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// The kinds of segments SponsorBlock can skip.
+///
+/// See the [SponsorBlock docs](https://wiki.sponsor.ajay.app/w/Segment_Categories) for what each
+/// of them covers.
+///
+/// NOTE: Requires SponsorBlock plugin.
+pub enum SegmentCategory {
+    Sponsor,
+    #[serde(rename = "selfpromo")]
+    SelfPromo,
+    Interaction,
+    Intro,
+    Outro,
+    Preview,
+    MusicOfftopic,
+    Filler,
+}
+
+impl SegmentCategory {
+    /// Every category, to skip everything SponsorBlock knows about.
+    pub const ALL: [SegmentCategory; 8] = [
+        SegmentCategory::Sponsor,
+        SegmentCategory::SelfPromo,
+        SegmentCategory::Interaction,
+        SegmentCategory::Intro,
+        SegmentCategory::Outro,
+        SegmentCategory::Preview,
+        SegmentCategory::MusicOfftopic,
+        SegmentCategory::Filler,
+    ];
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// A segment of a track that SponsorBlock skips.
+pub struct Segment {
+    /// The category of the segment.
+    pub category: SegmentCategory,
+    /// Where the segment starts, in milliseconds.
+    pub start: u64,
+    /// Where the segment ends, in milliseconds.
+    pub end: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(feature = "python", pyo3::pyclass(get_all, set_all))]
+/// A chapter of a track, as set by its uploader or the SponsorBlock community.
+pub struct Chapter {
+    /// The name of the chapter.
+    pub name: String,
+    /// Where the chapter starts, in milliseconds.
+    pub start: u64,
+    /// Where the chapter ends, in milliseconds.
+    pub end: u64,
+    /// How long the chapter lasts, in milliseconds.
+    pub duration: u64,
+}
@@ -1,4 +1,6 @@
+use crate::error::{LavalinkError, LavalinkResult};
 use crate::model::deserialize_option_number;
+use crate::model::lavasrc::{LavaSrcPlaylistInfo, LavaSrcTrackInfo};
 
 #[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
@@ -115,3 +117,563 @@ pub struct TrackError {
     /// The cause of the exception.
     pub cause: String,
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+/// A track in the Lavaplayer message format, which is what `TrackData::encoded` contains once
+/// decoded from base64.
+///
+/// This allows decoding and encoding tracks without a request to a Lavalink server.
+pub struct EncodedTrack {
+    /// The version of the format, from 1 to 3.
+    ///
+    /// Version 2 added the uri, and version 3 the artwork url and ISRC.
+    pub version: u8,
+    /// The information of the track.
+    ///
+    /// `is_seekable` is not part of the format, and is set to `!is_stream` when decoding.
+    pub info: TrackInfo,
+    /// Data written by the source manager of the track between the source name and the
+    /// position, like the container format of `http` and `local` tracks.
+    ///
+    /// Empty for most sources.
+    pub source_data: Vec<u8>,
+}
+
+/// Whether the message contains a version byte, set in the 2 upper bits of the header.
+const TRACK_INFO_VERSIONED: u32 = 1;
+
+impl EncodedTrack {
+    /// The latest version of the format, used when encoding new tracks.
+    pub const LATEST_VERSION: u8 = 3;
+
+    /// Create a track with the latest version of the format and no source specific data.
+    pub fn new(info: TrackInfo) -> Self {
+        Self {
+            version: Self::LATEST_VERSION,
+            info,
+            source_data: vec![],
+        }
+    }
+
+    /// Decode a base64 encoded track.
+    pub fn decode(encoded: &str) -> LavalinkResult<Self> {
+        use base64::Engine;
+
+        let bytes = base64::engine::general_purpose::STANDARD.decode(encoded)?;
+        let mut reader = MessageReader(&bytes);
+
+        let header = u32::from_be_bytes(reader.read_array()?);
+        let flags = header >> 30;
+        let size = (header & 0x3FFF_FFFF) as usize;
+
+        if size != reader.0.len() {
+            return Err(LavalinkError::InvalidEncodedTrack(format!(
+                "the header declares {} bytes, but the message has {}",
+                size,
+                reader.0.len()
+            )));
+        }
+
+        let version = if flags & TRACK_INFO_VERSIONED != 0 {
+            reader.read_array::<1>()?[0]
+        } else {
+            1
+        };
+
+        if !(1..=Self::LATEST_VERSION).contains(&version) {
+            return Err(LavalinkError::InvalidEncodedTrack(format!(
+                "unsupported version {}",
+                version
+            )));
+        }
+
+        let title = reader.read_utf()?;
+        let author = reader.read_utf()?;
+        let length = i64::from_be_bytes(reader.read_array()?) as u64;
+        let identifier = reader.read_utf()?;
+        let is_stream = reader.read_array::<1>()?[0] != 0;
+        let uri = if version >= 2 {
+            reader.read_nullable_utf()?
+        } else {
+            None
+        };
+        let (artwork_url, isrc) = if version >= 3 {
+            (reader.read_nullable_utf()?, reader.read_nullable_utf()?)
+        } else {
+            (None, None)
+        };
+        let source_name = reader.read_utf()?;
+
+        // The source specific data has no length, the position is always the last field.
+        let source_data_len = reader.0.len().checked_sub(8).ok_or_else(|| {
+            LavalinkError::InvalidEncodedTrack("missing the track position".to_string())
+        })?;
+        let source_data = reader.read_bytes(source_data_len)?.to_vec();
+        let position = i64::from_be_bytes(reader.read_array()?) as u64;
+
+        Ok(Self {
+            version,
+            info: TrackInfo {
+                identifier,
+                is_seekable: !is_stream,
+                author,
+                length,
+                is_stream,
+                position,
+                title,
+                uri,
+                artwork_url,
+                isrc,
+                source_name,
+            },
+            source_data,
+        })
+    }
+
+    /// Encode the track into base64.
+    ///
+    /// The fields that don't exist in the version of the track are left out.
+    pub fn encode(&self) -> LavalinkResult<String> {
+        use base64::Engine;
+
+        if !(1..=Self::LATEST_VERSION).contains(&self.version) {
+            return Err(LavalinkError::InvalidEncodedTrack(format!(
+                "unsupported version {}",
+                self.version
+            )));
+        }
+
+        let info = &self.info;
+        let mut message = vec![];
+
+        if self.version > 1 {
+            message.push(self.version);
+        }
+
+        write_utf(&mut message, &info.title)?;
+        write_utf(&mut message, &info.author)?;
+        message.extend_from_slice(&(info.length as i64).to_be_bytes());
+        write_utf(&mut message, &info.identifier)?;
+        message.push(info.is_stream as u8);
+
+        if self.version >= 2 {
+            write_nullable_utf(&mut message, info.uri.as_deref())?;
+        }
+
+        if self.version >= 3 {
+            write_nullable_utf(&mut message, info.artwork_url.as_deref())?;
+            write_nullable_utf(&mut message, info.isrc.as_deref())?;
+        }
+
+        write_utf(&mut message, &info.source_name)?;
+        message.extend_from_slice(&self.source_data);
+        message.extend_from_slice(&(info.position as i64).to_be_bytes());
+
+        let flags = if self.version > 1 {
+            TRACK_INFO_VERSIONED
+        } else {
+            0
+        };
+        let header = (flags << 30) | message.len() as u32;
+
+        let mut bytes = header.to_be_bytes().to_vec();
+        bytes.append(&mut message);
+
+        Ok(base64::engine::general_purpose::STANDARD.encode(bytes))
+    }
+}
+
+impl TrackData {
+    /// Decode a base64 encoded track without making a request to Lavalink.
+    ///
+    /// Unlike `decode_track`, `plugin_info` and `user_data` are always None, as they are not part
+    /// of the encoded track.
+    pub fn decode(encoded: &str) -> LavalinkResult<Self> {
+        let track = EncodedTrack::decode(encoded)?;
+
+        Ok(Self {
+            encoded: encoded.to_string(),
+            info: track.info,
+            plugin_info: None,
+            user_data: None,
+        })
+    }
+
+    /// Deserialize the plugin info into the type of a plugin.
+    ///
+    /// None if the track has no plugin info.
+    pub fn plugin_info_as<T: serde::de::DeserializeOwned>(&self) -> LavalinkResult<Option<T>> {
+        plugin_info_as(&self.plugin_info)
+    }
+
+    /// The info LavaSrc added to the track.
+    ///
+    /// None if the track has no plugin info, or it doesn't match the one of LavaSrc. Use
+    /// `plugin_info_as` to get why it doesn't match.
+    pub fn lavasrc_info(&self) -> Option<LavaSrcTrackInfo> {
+        self.plugin_info_as().ok().flatten()
+    }
+
+    /// Deserialize the user data into the type it was set with.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::MissingUserData` if the track has no user data, and
+    /// `LavalinkError::SerdeErrorJson` if it doesn't match the type.
+    pub fn user_data_as<T: serde::de::DeserializeOwned>(&self) -> LavalinkResult<T> {
+        let user_data = self
+            .user_data
+            .clone()
+            .ok_or(LavalinkError::MissingUserData)?;
+
+        Ok(serde_json::from_value(user_data)?)
+    }
+
+    /// Serialize the user data, replacing the previous one.
+    ///
+    /// It is sent to Lavalink when the track is played, and comes back in the events and players
+    /// that contain the track.
+    pub fn set_user_data<T: serde::Serialize>(&mut self, user_data: &T) -> LavalinkResult<()> {
+        self.user_data = Some(serde_json::to_value(user_data)?);
+
+        Ok(())
+    }
+
+    /// Build the track data from its info, encoding it with the latest version of the format.
+    ///
+    /// Tracks from sources that store extra data, like `http`, need to be built from an
+    /// `EncodedTrack` with the right `source_data` instead.
+    pub fn from_info(info: TrackInfo) -> LavalinkResult<Self> {
+        let encoded = EncodedTrack::new(info.clone()).encode()?;
+
+        Ok(Self {
+            encoded,
+            info,
+            plugin_info: None,
+            user_data: None,
+        })
+    }
+}
+
+impl PlaylistData {
+    /// Deserialize the plugin info into the type of a plugin.
+    ///
+    /// None if the playlist has no plugin info.
+    pub fn plugin_info_as<T: serde::de::DeserializeOwned>(&self) -> LavalinkResult<Option<T>> {
+        plugin_info_as(&self.plugin_info)
+    }
+
+    /// The info LavaSrc added to the playlist.
+    ///
+    /// None if the playlist has no plugin info, or it doesn't match the one of LavaSrc. Use
+    /// `plugin_info_as` to get why it doesn't match.
+    pub fn lavasrc_info(&self) -> Option<LavaSrcPlaylistInfo> {
+        self.plugin_info_as().ok().flatten()
+    }
+}
+
+/// Plugin info is an empty object when no plugin added anything.
+fn plugin_info_as<T: serde::de::DeserializeOwned>(
+    plugin_info: &Option<serde_json::Value>,
+) -> LavalinkResult<Option<T>> {
+    match plugin_info {
+        None | Some(serde_json::Value::Null) => Ok(None),
+        Some(serde_json::Value::Object(x)) if x.is_empty() => Ok(None),
+        Some(x) => Ok(Some(serde_json::from_value(x.clone())?)),
+    }
+}
+
+/// Reads the big-endian fields of a Java `DataOutput` message.
+struct MessageReader<'a>(&'a [u8]);
+
+impl<'a> MessageReader<'a> {
+    fn read_bytes(&mut self, len: usize) -> LavalinkResult<&'a [u8]> {
+        if self.0.len() < len {
+            return Err(LavalinkError::InvalidEncodedTrack(
+                "unexpected end of the message".to_string(),
+            ));
+        }
+
+        let (bytes, rest) = self.0.split_at(len);
+        self.0 = rest;
+
+        Ok(bytes)
+    }
+
+    fn read_array<const N: usize>(&mut self) -> LavalinkResult<[u8; N]> {
+        Ok(self.read_bytes(N)?.try_into().unwrap())
+    }
+
+    /// Reads a length prefixed string in modified UTF-8.
+    fn read_utf(&mut self) -> LavalinkResult<String> {
+        let len = u16::from_be_bytes(self.read_array()?) as usize;
+        let bytes = self.read_bytes(len)?;
+
+        let invalid = || LavalinkError::InvalidEncodedTrack("invalid modified UTF-8".to_string());
+
+        let mut units = Vec::with_capacity(len);
+        let mut iter = bytes.iter().map(|x| *x as u16);
+
+        while let Some(a) = iter.next() {
+            let unit = match a {
+                0x00..=0x7F => a,
+                0xC0..=0xDF => {
+                    let b = iter
+                        .next()
+                        .filter(|b| b & 0xC0 == 0x80)
+                        .ok_or_else(invalid)?;
+                    ((a & 0x1F) << 6) | (b & 0x3F)
+                }
+                0xE0..=0xEF => {
+                    let b = iter
+                        .next()
+                        .filter(|b| b & 0xC0 == 0x80)
+                        .ok_or_else(invalid)?;
+                    let c = iter
+                        .next()
+                        .filter(|c| c & 0xC0 == 0x80)
+                        .ok_or_else(invalid)?;
+                    ((a & 0x0F) << 12) | ((b & 0x3F) << 6) | (c & 0x3F)
+                }
+                _ => return Err(invalid()),
+            };
+
+            units.push(unit);
+        }
+
+        String::from_utf16(&units).map_err(|_| invalid())
+    }
+
+    fn read_nullable_utf(&mut self) -> LavalinkResult<Option<String>> {
+        if self.read_array::<1>()?[0] != 0 {
+            Ok(Some(self.read_utf()?))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+/// Writes a length prefixed string in modified UTF-8, where NUL is 2 bytes long and characters
+/// outside the BMP are written as surrogate pairs.
+fn write_utf(message: &mut Vec<u8>, value: &str) -> LavalinkResult<()> {
+    let mut bytes = vec![];
+
+    for unit in value.encode_utf16() {
+        match unit {
+            0x01..=0x7F => bytes.push(unit as u8),
+            0x00 | 0x80..=0x7FF => {
+                bytes.push(0xC0 | (unit >> 6) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+            _ => {
+                bytes.push(0xE0 | (unit >> 12) as u8);
+                bytes.push(0x80 | ((unit >> 6) & 0x3F) as u8);
+                bytes.push(0x80 | (unit & 0x3F) as u8);
+            }
+        }
+    }
+
+    let len = u16::try_from(bytes.len()).map_err(|_| {
+        LavalinkError::InvalidEncodedTrack(format!(
+            "a string is {} bytes long, the maximum is {}",
+            bytes.len(),
+            u16::MAX
+        ))
+    })?;
+
+    message.extend_from_slice(&len.to_be_bytes());
+    message.append(&mut bytes);
+
+    Ok(())
+}
+
+fn write_nullable_utf(message: &mut Vec<u8>, value: Option<&str>) -> LavalinkResult<()> {
+    if let Some(value) = value {
+        message.push(1);
+        write_utf(message, value)
+    } else {
+        message.push(0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example track of the Lavalink documentation.
+    const DOCS_TRACK: &str = "QAAAjQIAJVJpY2sgQXN0bGV5IC0gTmV2ZXIgR29ubmEgR2l2ZSBZb3UgVXAADlJpY2tBc3RsZXlWRVZPAAAAAAADPCAAC2RRdzR3OVdnWGNRAAEAK2h0dHBzOi8vd3d3LnlvdXR1YmUuY29tL3dhdGNoP3Y9ZFF3NHc5V2dYY1EAB3lvdXR1YmUAAAAAAAAAAA==";
+    const V3_TRACK: &str = "QAAA0wMAJVJpY2sgQXN0bGV5IC0gTmV2ZXIgR29ubmEgR2l2ZSBZb3UgVXAADlJpY2tBc3RsZXlWRVZPAAAAAAADPCAAC2RRdzR3OVdnWGNRAAEAK2h0dHBzOi8vd3d3LnlvdXR1YmUuY29tL3dhdGNoP3Y9ZFF3NHc5V2dYY1EBADRodHRwczovL2kueXRpbWcuY29tL3ZpL2RRdzR3OVdnWGNRL21heHJlc2RlZmF1bHQuanBnAQAMR0JBUkw5MzAwMTM1AAd5b3V0dWJlAAAAAAAAAAA=";
+    const V1_TRACK: &str = "AAAASwAcVGl0bGUgwIAgd2l0aCBudWwgYW5kIO2gvO2+tQAJQXV0aG9yIMOpAAAAAAAA6mAAA2FiYwAACnNvdW5kY2xvdWQAAAAAAAAAAA==";
+    const HTTP_TRACK: &str = "QAAAdwMABVJhZGlvAA5Vbmtub3duIGFydGlzdH//////////AB5odHRwczovL2V4YW1wbGUuY29tL3N0cmVhbS5tcDMBAQAeaHR0cHM6Ly9leGFtcGxlLmNvbS9zdHJlYW0ubXAzAAAABGh0dHAAA21wMwAAAAAAAATS";
+
+    fn rick_astley() -> TrackInfo {
+        TrackInfo {
+            identifier: "dQw4w9WgXcQ".to_string(),
+            is_seekable: true,
+            author: "RickAstleyVEVO".to_string(),
+            length: 212000,
+            is_stream: false,
+            position: 0,
+            title: "Rick Astley - Never Gonna Give You Up".to_string(),
+            uri: Some("https://www.youtube.com/watch?v=dQw4w9WgXcQ".to_string()),
+            artwork_url: None,
+            isrc: None,
+            source_name: "youtube".to_string(),
+        }
+    }
+
+    #[test]
+    fn decodes_version_2() {
+        let track = EncodedTrack::decode(DOCS_TRACK).unwrap();
+
+        assert_eq!(track.version, 2);
+        assert_eq!(track.info, rick_astley());
+        assert!(track.source_data.is_empty());
+    }
+
+    #[test]
+    fn decodes_version_3() {
+        let track = EncodedTrack::decode(V3_TRACK).unwrap();
+
+        assert_eq!(track.version, 3);
+        assert_eq!(
+            track.info,
+            TrackInfo {
+                artwork_url: Some(
+                    "https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg".to_string()
+                ),
+                isrc: Some("GBARL9300135".to_string()),
+                ..rick_astley()
+            }
+        );
+    }
+
+    #[test]
+    fn decodes_version_1_with_modified_utf8() {
+        let track = EncodedTrack::decode(V1_TRACK).unwrap();
+
+        assert_eq!(track.version, 1);
+        assert_eq!(track.info.title, "Title \u{0} with nul and \u{1F3B5}");
+        assert_eq!(track.info.author, "Author é");
+        assert_eq!(track.info.length, 60000);
+        assert_eq!(track.info.uri, None);
+        assert_eq!(track.info.source_name, "soundcloud");
+    }
+
+    #[test]
+    fn decodes_source_data_and_position() {
+        let track = EncodedTrack::decode(HTTP_TRACK).unwrap();
+
+        assert!(track.info.is_stream);
+        assert!(!track.info.is_seekable);
+        assert_eq!(track.info.length, i64::MAX as u64);
+        assert_eq!(track.info.position, 1234);
+        assert_eq!(track.source_data, b"\x00\x03mp3");
+    }
+
+    #[test]
+    fn round_trips_known_tracks() {
+        for encoded in [DOCS_TRACK, V3_TRACK, V1_TRACK, HTTP_TRACK] {
+            let track = EncodedTrack::decode(encoded).unwrap();
+            assert_eq!(track.encode().unwrap(), encoded);
+        }
+    }
+
+    #[test]
+    fn builds_track_data_from_info() {
+        let info = TrackInfo {
+            artwork_url: Some("https://i.ytimg.com/vi/dQw4w9WgXcQ/maxresdefault.jpg".to_string()),
+            isrc: Some("GBARL9300135".to_string()),
+            ..rick_astley()
+        };
+
+        let track = TrackData::from_info(info.clone()).unwrap();
+        assert_eq!(track.encoded, V3_TRACK);
+        assert_eq!(TrackData::decode(&track.encoded).unwrap(), track);
+    }
+
+    #[test]
+    fn reads_lavasrc_plugin_info() {
+        let track = serde_json::from_str::<TrackData>(&format!(
+            r#"{{"encoded":"{}","info":{},"pluginInfo":{{"albumName":"Whenever You Need Somebody","albumUrl":"https://open.spotify.com/album/6N9PS4QXF1D0OWPk0Sxtb4","artistUrl":"https://open.spotify.com/artist/0gxyHStUsqpMadRV0Di1Qt","artistArtworkUrl":"https://i.scdn.co/image/ab6761610000e5eb","previewUrl":null,"isPreview":false}},"userData":{{}}}}"#,
+            V3_TRACK,
+            serde_json::to_string(&rick_astley()).unwrap()
+        ))
+        .unwrap();
+
+        let info = track.lavasrc_info().unwrap();
+        assert_eq!(
+            info.album_name.as_deref(),
+            Some("Whenever You Need Somebody")
+        );
+        assert_eq!(info.preview_url, None);
+        assert!(!info.is_preview);
+
+        let youtube = TrackData {
+            plugin_info: Some(serde_json::json!({})),
+            ..TrackData::decode(V3_TRACK).unwrap()
+        };
+        assert_eq!(youtube.lavasrc_info(), None);
+
+        let mistyped = TrackData {
+            plugin_info: Some(serde_json::json!({ "isPreview": "no" })),
+            ..youtube
+        };
+        assert_eq!(mistyped.lavasrc_info(), None);
+        assert!(mistyped.plugin_info_as::<LavaSrcTrackInfo>().is_err());
+
+        let playlist = serde_json::from_value::<PlaylistData>(serde_json::json!({
+            "info": { "name": "Whenever You Need Somebody", "selectedTrack": -1 },
+            "pluginInfo": {
+                "type": "album",
+                "url": "https://open.spotify.com/album/6N9PS4QXF1D0OWPk0Sxtb4",
+                "artworkUrl": null,
+                "author": "Rick Astley",
+                "totalTracks": 10
+            },
+            "tracks": []
+        }))
+        .unwrap();
+
+        let info = playlist.lavasrc_info().unwrap();
+        assert_eq!(
+            info.kind,
+            Some(crate::model::lavasrc::LavaSrcPlaylistType::Album)
+        );
+        assert_eq!(info.total_tracks, Some(10));
+    }
+
+    #[test]
+    fn rejects_invalid_tracks() {
+        use base64::Engine;
+
+        let bytes = base64::engine::general_purpose::STANDARD
+            .decode(DOCS_TRACK)
+            .unwrap();
+        let encode = |bytes: &[u8]| base64::engine::general_purpose::STANDARD.encode(bytes);
+
+        assert!(EncodedTrack::decode("not base64!").is_err());
+        assert!(EncodedTrack::decode("").is_err());
+
+        for end in 0..bytes.len() {
+            assert!(EncodedTrack::decode(&encode(&bytes[..end])).is_err());
+        }
+
+        let mut unsupported = bytes.clone();
+        unsupported[4] = 4;
+        assert!(EncodedTrack::decode(&encode(&unsupported)).is_err());
+
+        let mut invalid_utf = bytes.clone();
+        invalid_utf[7] = 0xFF;
+        assert!(EncodedTrack::decode(&encode(&invalid_utf)).is_err());
+
+        let track = EncodedTrack {
+            info: TrackInfo {
+                title: "a".repeat(u16::MAX as usize + 1),
+                ..rick_astley()
+            },
+            ..EncodedTrack::new(Default::default())
+        };
+        assert!(track.encode().is_err());
+    }
+}
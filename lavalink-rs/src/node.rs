@@ -1,14 +1,16 @@
 use crate::client::LavalinkClient;
 use crate::error::LavalinkError;
-use crate::model::{events, BoxFuture, Secret, UserId};
+use crate::model::{events, BoxFuture, GuildId, Secret, UserId};
 
-use std::sync::atomic::{AtomicBool, Ordering};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
 use std::sync::Arc;
 
-use arc_swap::ArcSwap;
+use arc_swap::{ArcSwap, ArcSwapOption};
 use futures::stream::StreamExt;
 use http::HeaderMap;
 use http::Request;
+use tokio::sync::mpsc;
 use tokio_tungstenite::tungstenite::Message as TungsteniteMessage;
 use tokio_tungstenite::{connect_async, tungstenite::handshake::client::generate_key};
 
@@ -45,6 +47,16 @@ pub struct NodeBuilder {
     pub user_id: UserId,
     /// The previous Session ID if resuming.
     pub session_id: Option<String>,
+    /// How to reconnect to this node, overriding `ClientOptions::reconnect_policy`.
+    pub reconnect_policy: Option<crate::model::client::ReconnectPolicy>,
+    /// How to time out and retry REST requests to this node, overriding
+    /// `ClientOptions::request_policy`.
+    pub request_policy: Option<crate::model::client::RequestPolicy>,
+    /// The regions this node is in, like "europe" or "us-east".
+    ///
+    /// Used by `NodeDistributionStrategy::ByRegion` to pick the node closest to the Discord voice
+    /// server of a guild.
+    pub regions: Vec<String>,
 }
 
 #[derive(Debug)]
@@ -56,39 +68,51 @@ pub struct Node {
     pub http: crate::http::Http,
     pub events: events::Events,
     pub is_running: AtomicBool,
+    pub(crate) is_reconnecting: AtomicBool,
+    pub(crate) is_removed: AtomicBool,
+    pub(crate) connection: ArcSwapOption<tokio::task::AbortHandle>,
+    pub reconnect_policy: crate::model::client::ReconnectPolicy,
+    pub regions: Vec<String>,
     pub(crate) password: Secret,
     pub user_id: UserId,
     pub cpu: ArcSwap<crate::model::events::Cpu>,
     pub memory: ArcSwap<crate::model::events::Memory>,
+    /// The last stats sent by the node, None until it sends the first ones.
+    pub stats: ArcSwapOption<crate::model::events::Stats>,
 }
 
 #[derive(Copy, Clone)]
-struct EventDispatcher<'a>(&'a Node, &'a LavalinkClient);
+pub(crate) struct EventDispatcher<'a>(pub(crate) &'a Node, pub(crate) &'a LavalinkClient);
 
 // Thanks Alba :D
 impl<'a> EventDispatcher<'a> {
     pub(crate) async fn dispatch<T, F>(self, event: T, handler: F)
     where
         F: Fn(&events::Events) -> Option<fn(LavalinkClient, String, &T) -> BoxFuture<()>>,
+        T: Clone + Into<events::LavalinkEvent>,
     {
         let EventDispatcher(self_node, lavalink_client) = self;
-        let session_id = self_node.session_id.load_full();
-        let targets = [&self_node.events, &lavalink_client.events].into_iter();
 
-        for handler in targets.filter_map(handler) {
-            handler(lavalink_client.clone(), (*session_id).clone(), &event).await;
-        }
+        // Fails only when nobody is subscribed.
+        let _ = lavalink_client
+            .event_tx
+            .send((self_node.id, event.clone().into()));
+
+        self.dispatch_hooks(event, handler).await
     }
 
-    #[cfg(not(feature = "python"))]
-    pub(crate) async fn parse_and_dispatch<T, F>(self, event: &'a str, handler: F)
+    /// Dispatch the event to the hooks only, without sending it to the subscribers.
+    pub(crate) async fn dispatch_hooks<T, F>(self, event: T, handler: F)
     where
         F: Fn(&events::Events) -> Option<fn(LavalinkClient, String, &T) -> BoxFuture<()>>,
-        T: serde::Deserialize<'a>,
     {
-        trace!("{:?}", event);
-        let event = serde_json::from_str(event).unwrap();
-        self.dispatch(event, handler).await
+        let EventDispatcher(self_node, lavalink_client) = self;
+        let session_id = self_node.session_id.load_full();
+        let targets = [&self_node.events, &lavalink_client.events].into_iter();
+
+        for handler in targets.filter_map(handler) {
+            handler(lavalink_client.clone(), (*session_id).clone(), &event).await;
+        }
     }
 }
 
@@ -111,6 +135,7 @@ impl Node {
             let mut headers = HeaderMap::new();
             headers.insert("Authorization", self.password.0.parse()?);
             headers.insert("User-Id", self.user_id.0.to_string().parse()?);
+            // The session of the previous connection, so Lavalink can resume it if enabled.
             headers.insert("Session-Id", self.session_id.to_string().parse()?);
             headers.insert(
                 "Client-Name",
@@ -130,338 +155,476 @@ impl Node {
 
         self.is_running.store(true, Ordering::SeqCst);
 
+        EventDispatcher(self, &lavalink_client)
+            .dispatch(events::NodeConnected { node_id: self.id }, |e| {
+                e.node_connected
+            })
+            .await;
+
         let self_node_id = self.id;
 
-        tokio::spawn(async move {
+        let connection = tokio::spawn(async move {
+            let mut guild_workers = HashMap::<GuildId, GuildWorker>::new();
+
             while let Some(Ok(resp)) = read.next().await {
                 let x = match resp {
                     TungsteniteMessage::Text(x) => x,
                     _ => continue,
                 };
 
-                let base_event = match serde_json::from_str::<serde_json::Value>(&x) {
-                    Ok(base_event) => base_event,
-                    _ => continue,
-                };
+                let message = match serde_json::from_str::<events::IncomingMessage>(&x) {
+                    Ok(message) => message,
+                    Err(why) => {
+                        let lavalink_client = lavalink_client.clone();
 
-                let lavalink_client = lavalink_client.clone();
-
-                tokio::spawn(async move {
-                    let self_node = lavalink_client.nodes.get(self_node_id).unwrap();
-                    let ed = EventDispatcher(self_node, &lavalink_client);
-
-                    match base_event.get("op").unwrap().as_str().unwrap() {
-                        "ready" => {
-                            let ready_event: events::Ready = serde_json::from_str(&x).unwrap();
-
-                            self_node
-                                .session_id
-                                .swap(Arc::new(ready_event.session_id.to_string()));
-
-                            #[cfg(feature = "python")]
-                            {
-                                let session_id = self_node.session_id.load_full();
-
-                                if let Some(handler) = &self_node.events.event_handler {
-                                    handler
-                                        .event_ready(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            ready_event.clone(),
-                                        )
-                                        .await;
-                                }
-                                if let Some(handler) = &lavalink_client.events.event_handler {
-                                    handler
-                                        .event_ready(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            ready_event.clone(),
-                                        )
-                                        .await;
-                                }
+                        tokio::spawn(async move {
+                            if let Some(self_node) = lavalink_client.get_node_by_id(self_node_id) {
+                                let ed = EventDispatcher(&self_node, &lavalink_client);
+                                dispatch_invalid_message(ed, x, why).await;
                             }
+                        });
 
-                            ed.dispatch(ready_event, |e| e.ready).await;
-                        }
-                        "playerUpdate" => {
-                            let player_update_event: events::PlayerUpdate =
-                                serde_json::from_str(&x).unwrap();
-
-                            if let Some(player) =
-                                lavalink_client.get_player_context(player_update_event.guild_id)
-                            {
-                                if let Err(why) =
-                                    player.update_state(player_update_event.state.clone())
-                                {
-                                    error!(
-                                        "Error updating state for player {}: {}",
-                                        player_update_event.guild_id.0, why
-                                    );
-                                }
-                            }
-
-                            #[cfg(feature = "python")]
-                            {
-                                let session_id = self_node.session_id.load_full();
-
-                                if let Some(handler) = &self_node.events.event_handler {
-                                    handler
-                                        .event_player_update(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            player_update_event.clone(),
-                                        )
-                                        .await;
-                                }
-                                if let Some(handler) = &lavalink_client.events.event_handler {
-                                    handler
-                                        .event_player_update(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            player_update_event.clone(),
-                                        )
-                                        .await;
-                                }
-                            }
+                        continue;
+                    }
+                };
 
-                            ed.dispatch(player_update_event, |e| e.player_update).await;
-                        }
-                        "stats" => {
-                            #[cfg(feature = "python")]
-                            {
-                                let event: events::Stats = serde_json::from_str(&x).unwrap();
-                                let session_id = self_node.session_id.load_full();
-
-                                self_node.cpu.store(Arc::new(event.cpu.clone()));
-                                self_node.memory.store(Arc::new(event.memory.clone()));
-
-                                if let Some(handler) = &self_node.events.event_handler {
-                                    handler
-                                        .event_stats(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            event.clone(),
-                                        )
-                                        .await;
-                                }
-                                if let Some(handler) = &lavalink_client.events.event_handler {
-                                    handler
-                                        .event_stats(
-                                            lavalink_client.clone(),
-                                            (*session_id).clone(),
-                                            event.clone(),
-                                        )
-                                        .await;
-                                }
-
-                                ed.dispatch(event, |e| e.stats).await;
-                            }
-                            #[cfg(not(feature = "python"))]
-                            ed.parse_and_dispatch(&x, |e| e.stats).await;
+                match message.guild_id() {
+                    // Events of the same guild are handled one at a time, in the order they
+                    // arrived, while different guilds are handled concurrently.
+                    Some(guild_id) => {
+                        if !guild_workers.contains_key(&guild_id) {
+                            // Workers with nothing left to handle are stopped, so guilds that no
+                            // longer receive events don't keep theirs around.
+                            guild_workers.retain(|_, worker| worker.is_busy());
+                            guild_workers.insert(
+                                guild_id,
+                                GuildWorker::spawn(lavalink_client.clone(), self_node_id),
+                            );
                         }
-                        "event" => match base_event.get("type").unwrap().as_str().unwrap() {
-                            "TrackStartEvent" => {
-                                let track_event: events::TrackStart =
-                                    serde_json::from_str(&x).unwrap();
-
-                                if let Some(player) =
-                                    lavalink_client.get_player_context(track_event.guild_id)
-                                {
-                                    if let Err(why) =
-                                        player.update_track(track_event.track.clone().into())
-                                    {
-                                        error!(
-                                            "Error sending update track message for player {}: {}",
-                                            track_event.guild_id.0, why
-                                        );
-                                    }
-                                }
-
-                                #[cfg(feature = "python")]
-                                {
-                                    let session_id = self_node.session_id.load_full();
-
-                                    if let Some(handler) = &self_node.events.event_handler {
-                                        handler
-                                            .event_track_start(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                track_event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                    if let Some(handler) = &lavalink_client.events.event_handler {
-                                        handler
-                                            .event_track_start(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                track_event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                }
-
-                                ed.dispatch(track_event, |e| e.track_start).await;
-                            }
-                            "TrackEndEvent" => {
-                                let track_event: events::TrackEnd =
-                                    serde_json::from_str(&x).unwrap();
-
-                                if let Some(player) =
-                                    lavalink_client.get_player_context(track_event.guild_id)
-                                {
-                                    if let Err(why) =
-                                        player.finish(track_event.reason.clone().into())
-                                    {
-                                        error!(
-                                            "Error sending finish message for player {}: {}",
-                                            track_event.guild_id.0, why
-                                        );
-                                    }
-
-                                    if let Err(why) = player.update_track(None) {
-                                        error!(
-                                            "Error sending update track message for player {}: {}",
-                                            track_event.guild_id.0, why
-                                        );
-                                    }
-                                }
-
-                                #[cfg(feature = "python")]
-                                {
-                                    let session_id = self_node.session_id.load_full();
-
-                                    if let Some(handler) = &self_node.events.event_handler {
-                                        handler
-                                            .event_track_end(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                track_event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                    if let Some(handler) = &lavalink_client.events.event_handler {
-                                        handler
-                                            .event_track_end(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                track_event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                }
-
-                                ed.dispatch(track_event, |e| e.track_end).await;
-                            }
-                            "TrackExceptionEvent" => {
-                                #[cfg(feature = "python")]
-                                {
-                                    let event: events::TrackException =
-                                        serde_json::from_str(&x).unwrap();
-                                    let session_id = self_node.session_id.load_full();
-
-                                    if let Some(handler) = &self_node.events.event_handler {
-                                        handler
-                                            .event_track_exception(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                    if let Some(handler) = &lavalink_client.events.event_handler {
-                                        handler
-                                            .event_track_exception(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-
-                                    ed.dispatch(event, |e| e.track_exception).await;
-                                }
-                                #[cfg(not(feature = "python"))]
-                                ed.parse_and_dispatch(&x, |e| e.track_exception).await;
-                            }
-                            "TrackStuckEvent" => {
-                                #[cfg(feature = "python")]
-                                {
-                                    let event: events::TrackStuck =
-                                        serde_json::from_str(&x).unwrap();
-                                    let session_id = self_node.session_id.load_full();
-
-                                    if let Some(handler) = &self_node.events.event_handler {
-                                        handler
-                                            .event_track_stuck(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                    if let Some(handler) = &lavalink_client.events.event_handler {
-                                        handler
-                                            .event_track_stuck(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-
-                                    ed.dispatch(event, |e| e.track_stuck).await;
-                                }
-                                #[cfg(not(feature = "python"))]
-                                ed.parse_and_dispatch(&x, |e| e.track_stuck).await;
-                            }
-                            "WebSocketClosedEvent" => {
-                                #[cfg(feature = "python")]
-                                {
-                                    let event: events::WebSocketClosed =
-                                        serde_json::from_str(&x).unwrap();
-                                    let session_id = self_node.session_id.load_full();
-
-                                    if let Some(handler) = &self_node.events.event_handler {
-                                        handler
-                                            .event_websocket_closed(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-                                    if let Some(handler) = &lavalink_client.events.event_handler {
-                                        handler
-                                            .event_websocket_closed(
-                                                lavalink_client.clone(),
-                                                (*session_id).clone(),
-                                                event.clone(),
-                                            )
-                                            .await;
-                                    }
-
-                                    ed.dispatch(event, |e| e.websocket_closed).await;
-                                }
-                                #[cfg(not(feature = "python"))]
-                                ed.parse_and_dispatch(&x, |e| e.websocket_closed).await;
-                            }
-                            _ => (),
-                        },
 
-                        _ => (),
+                        guild_workers[&guild_id].send(x, message);
                     }
-
-                    ed.dispatch(base_event, |e| e.raw).await;
-                });
+                    None => {
+                        tokio::spawn(handle_message(
+                            lavalink_client.clone(),
+                            self_node_id,
+                            x,
+                            message,
+                        ));
+                    }
+                }
             }
 
-            let self_node = lavalink_client.nodes.get(self_node_id).unwrap();
+            // Stops the guild workers once they are done with the events they already received.
+            drop(guild_workers);
+
+            let Some(self_node) = lavalink_client.get_node_by_id(self_node_id) else {
+                // The node was removed from the client.
+                return;
+            };
             self_node.is_running.store(false, Ordering::SeqCst);
             error!("Connection Closed.");
+
+            EventDispatcher(&self_node, &lavalink_client)
+                .dispatch(
+                    events::NodeDisconnected {
+                        node_id: self_node_id,
+                        removed: false,
+                    },
+                    |e| e.node_disconnected,
+                )
+                .await;
+
+            lavalink_client.handle_node_lost(self_node_id).await;
         });
 
+        self.connection
+            .store(Some(Arc::new(connection.abort_handle())));
         Ok(())
     }
 }
+
+/// Handles the messages of a single guild in the order they were received.
+struct GuildWorker {
+    tx: mpsc::UnboundedSender<(String, events::IncomingMessage)>,
+    /// The amount of messages sent to the worker that it hasn't finished handling yet.
+    pending: Arc<AtomicUsize>,
+}
+
+impl GuildWorker {
+    fn spawn(lavalink_client: LavalinkClient, self_node_id: usize) -> Self {
+        let (tx, mut rx) = mpsc::unbounded_channel::<(String, events::IncomingMessage)>();
+        let pending = Arc::new(AtomicUsize::new(0));
+        let worker_pending = pending.clone();
+
+        tokio::spawn(async move {
+            while let Some((x, message)) = rx.recv().await {
+                handle_message(lavalink_client.clone(), self_node_id, x, message).await;
+                worker_pending.fetch_sub(1, Ordering::SeqCst);
+            }
+        });
+
+        Self { tx, pending }
+    }
+
+    fn send(&self, x: String, message: events::IncomingMessage) {
+        self.pending.fetch_add(1, Ordering::SeqCst);
+
+        if self.tx.send((x, message)).is_err() {
+            self.pending.fetch_sub(1, Ordering::SeqCst);
+        }
+    }
+
+    fn is_busy(&self) -> bool {
+        self.pending.load(Ordering::SeqCst) != 0
+    }
+}
+
+/// Handle a single message received from the websocket of a node.
+async fn handle_message(
+    lavalink_client: LavalinkClient,
+    self_node_id: usize,
+    x: String,
+    message: events::IncomingMessage,
+) {
+    let Some(self_node) = lavalink_client.get_node_by_id(self_node_id) else {
+        return;
+    };
+    let ed = EventDispatcher(&self_node, &lavalink_client);
+
+    // Events from a node the guild was migrated away from are stale.
+    if let Some(guild_id) = message.guild_id() {
+        if lavalink_client
+            .players
+            .get(&guild_id)
+            .is_some_and(|x| x.1.id != self_node_id)
+        {
+            trace!(
+                "Ignoring event for guild {:?} from node {}, which it is no longer bound to",
+                guild_id,
+                self_node_id
+            );
+            return;
+        }
+    }
+
+    match message {
+        events::IncomingMessage::Ready(ready_event) => {
+            self_node
+                .session_id
+                .swap(Arc::new(ready_event.session_id.to_string()));
+
+            lavalink_client.handle_ready(&self_node, &ready_event).await;
+
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_ready(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            ready_event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_ready(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            ready_event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(ready_event, |e| e.ready).await;
+        }
+        events::IncomingMessage::PlayerUpdate(player_update_event) => {
+            if let Some(player) = lavalink_client.get_player_context(player_update_event.guild_id) {
+                if let Err(why) = player.update_state(player_update_event.state.clone()) {
+                    error!(
+                        "Error updating state for player {}: {}",
+                        player_update_event.guild_id.0, why
+                    );
+                }
+            }
+
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_player_update(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            player_update_event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_player_update(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            player_update_event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(player_update_event, |e| e.player_update).await;
+        }
+        events::IncomingMessage::Stats(event) => {
+            self_node.cpu.store(Arc::new(event.cpu.clone()));
+            self_node.memory.store(Arc::new(event.memory.clone()));
+            self_node.stats.store(Some(Arc::new(event.clone())));
+
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_stats(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_stats(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(event, |e| e.stats).await;
+        }
+        events::IncomingMessage::Event(events::Event::TrackStart(track_event)) => {
+            if let Some(player) = lavalink_client.get_player_context(track_event.guild_id) {
+                if let Err(why) = player.update_track(track_event.track.clone().into()) {
+                    error!(
+                        "Error sending update track message for player {}: {}",
+                        track_event.guild_id.0, why
+                    );
+                }
+            }
+
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_track_start(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            track_event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_track_start(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            track_event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(track_event, |e| e.track_start).await;
+        }
+        events::IncomingMessage::Event(events::Event::TrackEnd(track_event)) => {
+            if let Some(player) = lavalink_client.get_player_context(track_event.guild_id) {
+                if let Err(why) =
+                    player.track_ended(track_event.reason.clone(), track_event.track.clone())
+                {
+                    error!(
+                        "Error sending finish message for player {}: {}",
+                        track_event.guild_id.0, why
+                    );
+                }
+
+                if let Err(why) = player.update_track(None) {
+                    error!(
+                        "Error sending update track message for player {}: {}",
+                        track_event.guild_id.0, why
+                    );
+                }
+            }
+
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_track_end(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            track_event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_track_end(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            track_event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(track_event, |e| e.track_end).await;
+        }
+        events::IncomingMessage::Event(events::Event::TrackException(event)) => {
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_track_exception(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_track_exception(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(event, |e| e.track_exception).await;
+        }
+        events::IncomingMessage::Event(events::Event::TrackStuck(event)) => {
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_track_stuck(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_track_stuck(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(event, |e| e.track_stuck).await;
+        }
+        events::IncomingMessage::Event(events::Event::WebSocketClosed(event)) => {
+            #[cfg(feature = "python")]
+            {
+                let session_id = self_node.session_id.load_full();
+
+                if let Some(handler) = &self_node.events.event_handler {
+                    handler
+                        .event_websocket_closed(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+                if let Some(handler) = &lavalink_client.events.event_handler {
+                    handler
+                        .event_websocket_closed(
+                            lavalink_client.clone(),
+                            (*session_id).clone(),
+                            event.clone(),
+                        )
+                        .await;
+                }
+            }
+
+            ed.dispatch(event, |e| e.websocket_closed).await;
+        }
+        events::IncomingMessage::Event(events::Event::LyricsFound(event)) => {
+            ed.dispatch(event, |e| e.lyrics_found).await;
+        }
+        events::IncomingMessage::Event(events::Event::LyricsNotFound(event)) => {
+            ed.dispatch(event, |e| e.lyrics_not_found).await;
+        }
+        events::IncomingMessage::Event(events::Event::LyricsLine(event)) => {
+            ed.dispatch(event, |e| e.lyrics_line).await;
+        }
+        events::IncomingMessage::Event(events::Event::SegmentsLoaded(event)) => {
+            ed.dispatch(event, |e| e.segments_loaded).await;
+        }
+        events::IncomingMessage::Event(events::Event::SegmentSkipped(event)) => {
+            ed.dispatch(event, |e| e.segment_skipped).await;
+        }
+        events::IncomingMessage::Event(events::Event::ChaptersLoaded(event)) => {
+            ed.dispatch(event, |e| e.chapters_loaded).await;
+        }
+        events::IncomingMessage::Event(events::Event::ChapterStarted(event)) => {
+            ed.dispatch(event, |e| e.chapter_started).await;
+        }
+        events::IncomingMessage::Event(events::Event::Unknown(_))
+        | events::IncomingMessage::Unknown(_) => {
+            trace!(
+                "Received an unknown message from node {}: {}",
+                self_node_id,
+                x
+            );
+        }
+    }
+
+    // Only the raw hooks need the message as a JSON value, so it's not built without them.
+    if self_node.events.raw.is_some() || lavalink_client.events.raw.is_some() {
+        if let Ok(base_event) = serde_json::from_str::<serde_json::Value>(&x) {
+            ed.dispatch_hooks(base_event, |e| e.raw).await;
+        }
+    }
+}
+
+/// Dispatch a message that could not be parsed, instead of handling it.
+async fn dispatch_invalid_message(
+    ed: EventDispatcher<'_>,
+    raw: String,
+    error: impl std::fmt::Display,
+) {
+    warn!(
+        "Received an invalid message from node {}: {}",
+        ed.0.id, error
+    );
+
+    let event = events::InvalidMessage {
+        raw,
+        error: error.to_string(),
+    };
+
+    ed.dispatch(event, |e| e.invalid_message).await;
+}
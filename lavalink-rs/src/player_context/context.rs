@@ -9,6 +9,8 @@ use std::task::Context;
 use std::task::Poll;
 
 use ::http::Method;
+use futures::stream::BoxStream;
+use tokio::sync::broadcast;
 use tokio::sync::mpsc::UnboundedSender;
 
 #[derive(Clone, Debug)]
@@ -43,6 +45,19 @@ impl PlayerContext {
         Ok(())
     }
 
+    /// Play the last track in the history again, putting the current track back at the front of
+    /// the queue.
+    ///
+    /// Returns the track that is going to play, or None if the history is empty, in which case
+    /// nothing changes.
+    pub async fn previous(&self) -> LavalinkResult<Option<super::TrackInQueue>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx.send(super::PlayerMessage::Previous(tx))?;
+
+        Ok(rx.await?)
+    }
+
     /// Finish the current track.
     ///
     /// # Parameters
@@ -54,6 +69,17 @@ impl PlayerContext {
         Ok(())
     }
 
+    /// Finish the current track because Lavalink reported it ended for this reason.
+    pub(crate) fn track_ended(
+        &self,
+        reason: events::TrackEndReason,
+        track: track::TrackData,
+    ) -> LavalinkResult<()> {
+        self.tx
+            .send(super::PlayerMessage::TrackEnded(reason, track))?;
+        Ok(())
+    }
+
     /// Update player data in the context.
     pub fn update_player_data(&self, player: player::Player) -> LavalinkResult<()> {
         self.tx.send(super::PlayerMessage::UpdatePlayer(player))?;
@@ -73,25 +99,36 @@ impl PlayerContext {
     }
 
     /// Add a track to the end of the queue.
-    pub fn queue(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
-        let q = self.get_queue();
-        q.send(super::QueueMessage::PushToBack(track.into()))
+    pub async fn queue(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
+        self.get_queue().push_to_back(track).await
     }
 
     /// Get a reference to the current queue.
+    ///
+    /// Iterating through it as a Stream yields the tracks of a snapshot taken on the first poll,
+    /// so changes made to the queue meanwhile don't skip or repeat tracks.
     pub fn get_queue(&self) -> QueueRef {
-        let stream = futures::stream::unfold((0, self.tx.clone()), |(idx, outer_tx)| async move {
-            let (tx, rx) = oneshot::channel();
-
-            let _ = outer_tx.send(super::PlayerMessage::QueueMessage(
-                super::QueueMessage::GetTrack(idx, tx),
-            ));
-
-            rx.await
-                .ok()
-                .flatten()
-                .map(|track| (track, (idx + 1, outer_tx)))
-        });
+        let stream = futures::stream::unfold(
+            (None, self.tx.clone()),
+            |(tracks, outer_tx): (Option<VecDeque<super::TrackInQueue>>, _)| async move {
+                let mut tracks = match tracks {
+                    Some(tracks) => tracks,
+                    None => {
+                        let (tx, rx) = oneshot::channel();
+
+                        let _ = outer_tx.send(super::PlayerMessage::QueueMessage(
+                            super::QueueMessage::GetQueue(tx),
+                        ));
+
+                        rx.await.ok()?
+                    }
+                };
+
+                tracks
+                    .pop_front()
+                    .map(|track| (track, (Some(tracks), outer_tx)))
+            },
+        );
 
         QueueRef {
             tx: self.tx.clone(),
@@ -239,6 +276,73 @@ impl PlayerContext {
         .await
     }
 
+    /// Change some filters of the player, keeping the ones that are already active.
+    ///
+    /// The closure receives a builder holding the current filters of the player,
+    /// setting a filter to `None` disables it. Updates are applied one after the other, along with
+    /// the steps of the fades, so none of them is lost.
+    pub async fn update_filters(
+        &self,
+        update: impl FnOnce(player::FiltersBuilder) -> player::FiltersBuilder + Send + 'static,
+    ) -> LavalinkResult<player::Player> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx
+            .send(super::PlayerMessage::UpdateFilters(Box::new(update), tx))?;
+
+        rx.await?
+    }
+
+    /// Smoothly change the volume filter to a percentage, from 0 to 500, over some time.
+    ///
+    /// Resolves once the volume is reached, or once the fade is interrupted by another one or by
+    /// the track changing.
+    pub async fn fade_to(&self, volume: u16, duration: std::time::Duration) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx.send(super::PlayerMessage::FadeTo(
+            volume.min(500) as f64 / 100.0,
+            duration,
+            tx,
+        ))?;
+
+        // Being interrupted is not an error.
+        let _ = rx.await;
+
+        Ok(())
+    }
+
+    /// Fade out the current track, then skip it.
+    ///
+    /// The next track plays at the volume from before the fade.
+    pub async fn skip_with_fade(&self, duration: std::time::Duration) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx.send(super::PlayerMessage::FadeOut(duration, tx))?;
+
+        // The track already changed if the fade was interrupted.
+        if rx.await.is_ok() {
+            self.skip()?;
+        }
+
+        Ok(())
+    }
+
+    /// Fade out the current track, then stop playing it.
+    ///
+    /// The next track plays at the volume from before the fade.
+    pub async fn stop_with_fade(&self, duration: std::time::Duration) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.tx.send(super::PlayerMessage::FadeOut(duration, tx))?;
+
+        if rx.await.is_ok() {
+            self.stop_now().await?;
+        }
+
+        Ok(())
+    }
+
     /// Jump to a specific position in the currently playing track.
     pub async fn set_position(
         &self,
@@ -254,6 +358,81 @@ impl PlayerContext {
         .await
     }
 
+    /// Load the lyrics of the track that is currently playing.
+    ///
+    /// None if no lyrics were found.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub async fn get_lyrics(&self) -> LavalinkResult<Option<lyrics::Lyrics>> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .get_player_lyrics(self.guild_id, &node.session_id.load(), false)
+            .await
+    }
+
+    /// Receive the lyrics events for every track this player plays, until
+    /// `unsubscribe_lyrics` is called.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub async fn subscribe_lyrics(&self) -> LavalinkResult<()> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .subscribe_lyrics(self.guild_id, &node.session_id.load(), false)
+            .await
+    }
+
+    /// Stop receiving the lyrics events for this player.
+    ///
+    /// NOTE: Requires LavaLyrics plugin.
+    pub async fn unsubscribe_lyrics(&self) -> LavalinkResult<()> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .unsubscribe_lyrics(self.guild_id, &node.session_id.load())
+            .await
+    }
+
+    /// Get the SponsorBlock categories this player skips.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn get_sponsorblock_categories(
+        &self,
+    ) -> LavalinkResult<Vec<sponsorblock::SegmentCategory>> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .get_sponsorblock_categories(self.guild_id, &node.session_id.load())
+            .await
+    }
+
+    /// Skip the SponsorBlock segments of these categories in the tracks this player plays,
+    /// replacing the previous categories.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn set_sponsorblock_categories(
+        &self,
+        categories: &[sponsorblock::SegmentCategory],
+    ) -> LavalinkResult<()> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .set_sponsorblock_categories(self.guild_id, &node.session_id.load(), categories)
+            .await
+    }
+
+    /// Stop skipping SponsorBlock segments.
+    ///
+    /// NOTE: Requires SponsorBlock plugin.
+    pub async fn clear_sponsorblock_categories(&self) -> LavalinkResult<()> {
+        let node = self.client.get_node_for_guild(self.guild_id).await;
+
+        node.http
+            .delete_sponsorblock_categories(self.guild_id, &node.session_id.load())
+            .await
+    }
+
     /// Get the custom data provided when creating the player context.
     ///
     /// # Errors
@@ -280,6 +459,52 @@ impl QueueRef {
         Ok(rx.await?)
     }
 
+    /// Get the current queue along with its revision, which `QueueUpdate::revision` can be
+    /// compared against.
+    ///
+    /// Note: This clones the entire queue.
+    pub async fn snapshot(&self) -> LavalinkResult<super::QueueSnapshot> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::GetSnapshot(tx))?;
+
+        Ok(rx.await?)
+    }
+
+    /// Get a stream of the changes made to the queue.
+    ///
+    /// Like a `watch` channel, the first update is a `QueueChange::Reset` with the current queue,
+    /// followed by every change made after it. A subscriber that falls too far behind gets a new
+    /// `QueueChange::Reset` instead of the changes it missed. The stream ends when the player is
+    /// closed.
+    pub fn changes(&self) -> BoxStream<'static, super::QueueUpdate> {
+        let stream =
+            futures::stream::unfold((None, self.tx.clone()), |(rx, outer_tx)| async move {
+                let mut rx: broadcast::Receiver<super::QueueUpdate> = match rx {
+                    Some(rx) => rx,
+                    None => {
+                        let (update, rx) = subscribe_to_queue(&outer_tx).await?;
+                        return Some((update, (Some(rx), outer_tx)));
+                    }
+                };
+
+                match rx.recv().await {
+                    Ok(update) => Some((update, (Some(rx), outer_tx))),
+                    Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!(
+                            "Queue subscriber lagged behind, skipped {} updates",
+                            skipped
+                        );
+                        let (update, rx) = subscribe_to_queue(&outer_tx).await?;
+                        Some((update, (Some(rx), outer_tx)))
+                    }
+                    Err(broadcast::error::RecvError::Closed) => None,
+                }
+            });
+
+        Box::pin(stream)
+    }
+
     /// Get the track at the index.
     ///
     /// Note: This clones the track.
@@ -300,48 +525,233 @@ impl QueueRef {
         Ok(rx.await?)
     }
 
+    /// Get the tracks that already played, from oldest to newest.
+    ///
+    /// Only the last `HISTORY_LIMIT` tracks are kept. A track that repeats with
+    /// `RepeatMode::Track` is only added once it stops repeating.
+    pub async fn history(&self) -> LavalinkResult<VecDeque<super::TrackInQueue>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::GetHistory(tx))?;
+
+        Ok(rx.await?)
+    }
+
     /// Add the track at the end of the queue.
-    pub fn push_to_back(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::PushToBack(track.into()))
+    pub async fn push_to_back(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::PushToBack(track.into(), tx))?;
+
+        Ok(rx.await?)
     }
 
     /// Add the track at the start of the queue.
-    pub fn push_to_front(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::PushToFront(track.into()))
+    pub async fn push_to_front(&self, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::PushToFront(track.into(), tx))?;
+
+        Ok(rx.await?)
     }
 
     /// Insert the track at the given index.
-    pub fn insert(
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if the index is greater than the queue length.
+    pub async fn insert(
         &self,
         index: usize,
         track: impl Into<super::TrackInQueue>,
     ) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Insert(index, track.into()))
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Insert(index, track.into(), tx))?;
+
+        rx.await?
     }
 
-    /// Remove the track at the given index.
-    pub fn remove(&self, index: usize) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Remove(index))
+    /// Remove the track at the given index and return it.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if there is no track at the index.
+    pub async fn remove(&self, index: usize) -> LavalinkResult<super::TrackInQueue> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Remove(index, tx))?;
+
+        rx.await?
+    }
+
+    /// Remove the tracks in the range and return them.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if the range ends past the end of the queue.
+    /// A range that starts after it ends removes nothing.
+    pub async fn remove_range(
+        &self,
+        range: impl std::ops::RangeBounds<usize>,
+    ) -> LavalinkResult<VecDeque<super::TrackInQueue>> {
+        let range = (range.start_bound().cloned(), range.end_bound().cloned());
+
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::RemoveRange(range, tx))?;
+
+        rx.await?
+    }
+
+    /// Move the track at `from` so it ends up at `to`, shifting the tracks in between.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if either index is not in the queue.
+    pub async fn move_track(&self, from: usize, to: usize) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Move(from, to, tx))?;
+
+        rx.await?
+    }
+
+    /// Keep only the tracks the predicate returns true for, and return the removed ones.
+    ///
+    /// The predicate runs inside the player, so the queue can't change while it's filtered.
+    pub async fn retain(
+        &self,
+        predicate: impl FnMut(&super::TrackInQueue) -> bool + Send + 'static,
+    ) -> LavalinkResult<VecDeque<super::TrackInQueue>> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Retain(
+            super::TrackPredicate(Box::new(predicate)),
+            tx,
+        ))?;
+
+        Ok(rx.await?)
+    }
+
+    /// Remove the tracks that have the same source and identifier as a track earlier in the
+    /// queue, and return them.
+    pub async fn dedupe_by_identifier(&self) -> LavalinkResult<VecDeque<super::TrackInQueue>> {
+        let mut seen = std::collections::HashSet::new();
+
+        self.retain(move |track| {
+            seen.insert((
+                track.track.info.source_name.clone(),
+                track.track.info.identifier.clone(),
+            ))
+        })
+        .await
+    }
+
+    /// Remove the tracks whose user data matches `filter`, and return them.
+    ///
+    /// If `filter` is an object, the user data has to contain every field of it with the same
+    /// value, so `json!({"requester_id": 1})` drains every track requested by that user.
+    /// Otherwise, the user data has to be equal to it.
+    pub async fn drain_by_user_data(
+        &self,
+        filter: serde_json::Value,
+    ) -> LavalinkResult<VecDeque<super::TrackInQueue>> {
+        self.retain(move |track| {
+            let Some(user_data) = &track.track.user_data else {
+                return true;
+            };
+
+            let matches = match (&filter, user_data) {
+                (serde_json::Value::Object(filter), serde_json::Value::Object(user_data)) => filter
+                    .iter()
+                    .all(|(key, value)| user_data.get(key) == Some(value)),
+                (filter, user_data) => filter == user_data,
+            };
+
+            !matches
+        })
+        .await
     }
 
     /// Clear the queue.
-    pub fn clear(&self) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Clear)
+    pub async fn clear(&self) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Clear(tx))?;
+
+        Ok(rx.await?)
     }
 
     /// Replace the entire queue with a new one.
-    pub fn replace(&self, tracks: VecDeque<super::TrackInQueue>) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Replace(tracks))
+    pub async fn replace(&self, tracks: VecDeque<super::TrackInQueue>) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Replace(tracks, tx))?;
+
+        Ok(rx.await?)
     }
 
     /// Append the list at the end of the current queue.
-    pub fn append(&self, tracks: VecDeque<super::TrackInQueue>) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Append(tracks))
+    pub async fn append(&self, tracks: VecDeque<super::TrackInQueue>) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Append(tracks, tx))?;
+
+        Ok(rx.await?)
+    }
+
+    /// Swap the track at the index with a new track, and return the old one.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if there is no track at the index.
+    pub async fn swap(
+        &self,
+        index: usize,
+        track: impl Into<super::TrackInQueue>,
+    ) -> LavalinkResult<super::TrackInQueue> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Swap(index, track.into(), tx))?;
+
+        rx.await?
     }
 
-    /// Swap the track at the index with a new track.
-    pub fn swap(&self, index: usize, track: impl Into<super::TrackInQueue>) -> LavalinkResult<()> {
-        self.send(super::QueueMessage::Swap(index, track.into()))
+    /// Exchange the positions of the tracks at the two indexes.
+    ///
+    /// # Errors
+    /// Returns `LavalinkError::IndexOutOfBounds` if either index is not in the queue.
+    pub async fn swap_indices(&self, first: usize, second: usize) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::SwapIndices(first, second, tx))?;
+
+        rx.await?
+    }
+
+    /// Shuffle the tracks in the queue.
+    ///
+    /// The current track is not part of the queue, so it keeps playing.
+    pub async fn shuffle(&self) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::Shuffle(tx))?;
+
+        Ok(rx.await?)
+    }
+
+    /// Get the repeat mode of the player.
+    pub async fn get_repeat_mode(&self) -> LavalinkResult<super::RepeatMode> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::GetRepeatMode(tx))?;
+
+        Ok(rx.await?)
+    }
+
+    /// Set what to play once the current track finishes.
+    pub async fn set_repeat_mode(&self, repeat_mode: super::RepeatMode) -> LavalinkResult<()> {
+        let (tx, rx) = oneshot::channel();
+
+        self.send(super::QueueMessage::SetRepeatMode(repeat_mode, tx))?;
+
+        Ok(rx.await?)
     }
 
     /// Send messages to the queue to obtain tracks from it, or modify it.
@@ -352,6 +762,30 @@ impl QueueRef {
     }
 }
 
+/// Subscribe to the queue, returning a reset to its current state as the first update.
+async fn subscribe_to_queue(
+    outer_tx: &UnboundedSender<super::PlayerMessage>,
+) -> Option<(super::QueueUpdate, broadcast::Receiver<super::QueueUpdate>)> {
+    let (tx, rx) = oneshot::channel();
+
+    outer_tx
+        .send(super::PlayerMessage::QueueMessage(
+            super::QueueMessage::Subscribe(tx),
+        ))
+        .ok()?;
+
+    let (snapshot, rx) = rx.await.ok()?;
+
+    let update = super::QueueUpdate {
+        revision: snapshot.revision,
+        change: super::QueueChange::Reset {
+            tracks: snapshot.tracks,
+        },
+    };
+
+    Some((update, rx))
+}
+
 impl futures::Stream for QueueRef {
     type Item = super::TrackInQueue;
 
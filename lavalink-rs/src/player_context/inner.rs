@@ -1,8 +1,36 @@
+use crate::client::LavalinkClient;
+use crate::error::{LavalinkError, LavalinkResult};
 use crate::model::*;
 
 use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::Duration;
 
-use tokio::sync::mpsc::UnboundedReceiver;
+use tokio::sync::broadcast;
+use tokio::sync::mpsc::{self, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinHandle;
+
+/// An event of the player, waiting to be dispatched through the node the player is bound to.
+pub(crate) type QueuedEvent =
+    Box<dyn FnOnce(Arc<crate::node::Node>, LavalinkClient) -> BoxFuture<'static, ()> + Send>;
+
+#[derive(PartialEq, Eq, Debug, Clone, Copy)]
+pub(crate) enum FadeKind {
+    /// The fade in at the start of a track.
+    In,
+    /// The fade out scheduled before the end of a track, moved with every player update.
+    Out,
+    /// A fade requested through the player context.
+    Manual,
+}
+
+/// A running or scheduled ramp of the volume filter.
+pub(crate) struct Fade {
+    pub kind: FadeKind,
+    /// The volume filter the fade ends at.
+    pub target: Option<f64>,
+    pub handle: JoinHandle<()>,
+}
 
 pub(crate) struct PlayerContextInner {
     pub guild_id: GuildId,
@@ -10,9 +38,51 @@ pub(crate) struct PlayerContextInner {
     pub player_data: player::Player,
     pub dummy: super::PlayerContext,
     pub last_should_continue: bool,
+    pub repeat_mode: super::RepeatMode,
+    /// The last track started from the queue, used to repeat it.
+    pub current_track: Option<super::TrackInQueue>,
+    pub history: VecDeque<super::TrackInQueue>,
+    /// How many changes were made to the queue, bumped with every `QueueUpdate` sent.
+    pub queue_revision: u64,
+    pub queue_tx: broadcast::Sender<super::QueueUpdate>,
+    pub fade: Option<Fade>,
+    /// The volume filter to go back to once a faded out track is over.
+    pub restore_volume: Option<Option<f64>>,
+    /// When the current track started according to the node, to ignore older player updates.
+    pub track_started_at: u64,
+    /// The track Lavalink last reported as ended, which is not in `current_track` if it was
+    /// played without the queue.
+    pub ended_track: Option<track::TrackData>,
+    /// The events of the player, dispatched one at a time in the order they were sent.
+    pub event_tx: UnboundedSender<QueuedEvent>,
 }
 
 impl PlayerContextInner {
+    /// Start the task dispatching the events of the player, which stops once the player does.
+    pub fn spawn_event_task(
+        client: LavalinkClient,
+        guild_id: GuildId,
+    ) -> UnboundedSender<QueuedEvent> {
+        let (tx, mut rx) = mpsc::unbounded_channel::<QueuedEvent>();
+
+        tokio::spawn(async move {
+            while let Some(event) = rx.recv().await {
+                // Selecting a node just to dispatch an event would affect the node distribution.
+                let Some(node) = client.players.get(&guild_id).map(|x| x.1.clone()) else {
+                    trace!(
+                        "Dropping an event of player {}, which is no longer bound to a node",
+                        guild_id.0
+                    );
+                    continue;
+                };
+
+                event(node, client.clone()).await;
+            }
+        });
+
+        tx
+    }
+
     pub async fn start(mut self, mut rx: UnboundedReceiver<super::PlayerMessage>) {
         tokio::spawn(async move {
             while let Some(x) = rx.recv().await {
@@ -29,10 +99,26 @@ impl PlayerContextInner {
                     }
                     UpdatePlayer(player) => self.player_data = player,
                     UpdatePlayerTrack(track) => self.player_data.track = track,
-                    UpdatePlayerState(state) => self.player_data.state = state,
+                    UpdateFilters(update, tx) => {
+                        let result = self.update_filters(update).await;
+                        self.reply(tx, result, "player");
+                    }
+                    UpdatePlayerState(state) => {
+                        let position = Duration::from_millis(state.position);
+                        let is_current = state.time >= self.track_started_at;
+
+                        self.player_data.state = state;
+
+                        if is_current {
+                            self.schedule_fade_out(position);
+                        }
+                    }
 
                     QueueMessage(queue_message) => {
-                        self.queue_init().await;
+                        // Reading the queue must not start playing it.
+                        if !queue_message.is_read_only() {
+                            self.queue_init().await;
+                        }
 
                         use super::QueueMessage::*;
 
@@ -45,6 +131,14 @@ impl PlayerContextInner {
                                     );
                                 }
                             }
+                            GetSnapshot(tx) => {
+                                let snapshot = self.snapshot();
+                                self.reply(tx, snapshot, "queue snapshot");
+                            }
+                            Subscribe(tx) => {
+                                let subscription = (self.snapshot(), self.queue_tx.subscribe());
+                                self.reply(tx, subscription, "queue subscription");
+                            }
                             GetTrack(index, tx) => {
                                 let track = self.queue.get(index).cloned();
 
@@ -63,60 +157,334 @@ impl PlayerContextInner {
                                     );
                                 }
                             }
-                            PushToBack(track) => {
-                                self.queue.push_back(track);
+                            GetHistory(tx) => {
+                                if let Err(why) = tx.send(self.history.clone()) {
+                                    error!(
+                                        "Error sending history back to the player {}: {}",
+                                        self.guild_id.0, why
+                                    );
+                                }
+                            }
+                            PushToBack(track, tx) => {
+                                self.track_queued(self.queue.len(), [&track]);
+                                self.push_back(track);
+                                self.reply(tx, (), "acknowledgement");
+                            }
+                            PushToFront(track, tx) => {
+                                self.track_queued(0, [&track]);
+                                self.push_front(track);
+                                self.reply(tx, (), "acknowledgement");
                             }
-                            PushToFront(track) => {
-                                self.queue.push_front(track);
+                            Insert(index, track, tx) => {
+                                let result = self.check_index(index, self.queue.len() + 1);
+
+                                if result.is_ok() {
+                                    self.track_queued(index, [&track]);
+                                    self.queue_changed(super::QueueChange::Added {
+                                        index,
+                                        tracks: vec![track.clone()],
+                                    });
+                                    self.queue.insert(index, track);
+                                }
+
+                                self.reply(tx, result, "acknowledgement");
+                            }
+                            Remove(index, tx) => {
+                                let len = self.queue.len();
+
+                                let result = self
+                                    .queue
+                                    .remove(index)
+                                    .ok_or(LavalinkError::IndexOutOfBounds { index, len });
+
+                                if let Ok(track) = &result {
+                                    self.queue_changed(super::QueueChange::Removed {
+                                        index,
+                                        tracks: vec![track.clone()],
+                                    });
+                                }
+
+                                self.reply(tx, result, "removed track");
+                            }
+                            RemoveRange((start, end), tx) => {
+                                use std::ops::Bound;
+
+                                let start = match start {
+                                    Bound::Included(x) => x,
+                                    Bound::Excluded(x) => x.saturating_add(1),
+                                    Bound::Unbounded => 0,
+                                };
+                                let end = match end {
+                                    Bound::Included(x) => x.saturating_add(1),
+                                    Bound::Excluded(x) => x,
+                                    Bound::Unbounded => self.queue.len(),
+                                };
+
+                                let result = if end > self.queue.len() {
+                                    Err(LavalinkError::IndexOutOfBounds {
+                                        index: end - 1,
+                                        len: self.queue.len(),
+                                    })
+                                } else {
+                                    let start = start.min(end);
+                                    let tracks =
+                                        self.queue.drain(start..end).collect::<VecDeque<_>>();
+
+                                    if !tracks.is_empty() {
+                                        self.queue_changed(super::QueueChange::Removed {
+                                            index: start,
+                                            tracks: tracks.iter().cloned().collect(),
+                                        });
+                                    }
+
+                                    Ok(tracks)
+                                };
+
+                                self.reply(tx, result, "removed tracks");
                             }
-                            Insert(index, track) => {
-                                self.queue.insert(index, track);
+                            Move(from, to, tx) => {
+                                let result = self
+                                    .check_index(from, self.queue.len())
+                                    .and_then(|_| self.check_index(to, self.queue.len()));
+
+                                if result.is_ok() && from != to {
+                                    let track = self.queue.remove(from).unwrap();
+                                    self.queue.insert(to, track);
+                                    self.queue_changed(super::QueueChange::Moved { from, to });
+                                }
+
+                                self.reply(tx, result, "acknowledgement");
                             }
-                            Remove(index) => {
-                                self.queue.remove(index);
+                            Retain(mut predicate, tx) => {
+                                let keep = self
+                                    .queue
+                                    .iter()
+                                    .map(|track| (predicate.0)(track))
+                                    .collect::<Vec<_>>();
+
+                                let mut removed = VecDeque::new();
+                                let mut end = keep.len();
+
+                                // Removing the runs of tracks from the back keeps the indexes of
+                                // the earlier ones valid.
+                                while end > 0 {
+                                    if keep[end - 1] {
+                                        end -= 1;
+                                        continue;
+                                    }
+
+                                    let mut start = end - 1;
+
+                                    while start > 0 && !keep[start - 1] {
+                                        start -= 1;
+                                    }
+
+                                    let tracks = self.queue.drain(start..end).collect::<Vec<_>>();
+
+                                    for track in tracks.iter().rev() {
+                                        removed.push_front(track.clone());
+                                    }
+
+                                    self.queue_changed(super::QueueChange::Removed {
+                                        index: start,
+                                        tracks,
+                                    });
+
+                                    end = start;
+                                }
+
+                                self.reply(tx, removed, "removed tracks");
                             }
-                            Clear => {
-                                self.queue.clear();
+                            Clear(tx) => {
+                                if !self.queue.is_empty() {
+                                    self.queue.clear();
+                                    self.queue_changed(super::QueueChange::Cleared);
+                                }
+
+                                self.reply(tx, (), "acknowledgement");
                             }
-                            Replace(tracks) => {
+                            Replace(tracks, tx) => {
                                 self.queue = tracks;
+                                self.queue_changed(super::QueueChange::Reset {
+                                    tracks: self.queue.clone(),
+                                });
+                                self.reply(tx, (), "acknowledgement");
+                            }
+                            Append(mut tracks, tx) => {
+                                if !tracks.is_empty() {
+                                    self.track_queued(self.queue.len(), &tracks);
+                                    self.queue_changed(super::QueueChange::Added {
+                                        index: self.queue.len(),
+                                        tracks: tracks.iter().cloned().collect(),
+                                    });
+                                    self.queue.append(&mut tracks);
+                                }
+
+                                self.reply(tx, (), "acknowledgement");
+                            }
+                            Swap(index, track, tx) => {
+                                let len = self.queue.len();
+
+                                let result = match self.queue.get_mut(index) {
+                                    Some(t) => Ok(std::mem::replace(t, track.clone())),
+                                    None => Err(LavalinkError::IndexOutOfBounds { index, len }),
+                                };
+
+                                if result.is_ok() {
+                                    self.queue_changed(super::QueueChange::Replaced {
+                                        index,
+                                        track: Box::new(track),
+                                    });
+                                }
+
+                                self.reply(tx, result, "replaced track");
                             }
-                            Append(mut tracks) => {
-                                self.queue.append(&mut tracks);
+                            SwapIndices(first, second, tx) => {
+                                let result = self
+                                    .check_index(first, self.queue.len())
+                                    .and_then(|_| self.check_index(second, self.queue.len()));
+
+                                if result.is_ok() && first != second {
+                                    self.queue.swap(first, second);
+                                    self.queue_changed(super::QueueChange::Swapped {
+                                        first,
+                                        second,
+                                    });
+                                }
+
+                                self.reply(tx, result, "acknowledgement");
                             }
-                            Swap(index, track) => {
-                                if let Some(t) = self.queue.get_mut(index) {
-                                    *t = track;
+                            Shuffle(tx) => {
+                                use rand::seq::SliceRandom;
+
+                                self.queue
+                                    .make_contiguous()
+                                    .shuffle(&mut rand::thread_rng());
+
+                                self.queue_changed(super::QueueChange::Reset {
+                                    tracks: self.queue.clone(),
+                                });
+                                self.reply(tx, (), "acknowledgement");
+                            }
+                            GetRepeatMode(tx) => {
+                                if let Err(why) = tx.send(self.repeat_mode) {
+                                    error!(
+                                        "Error sending repeat mode back to the player {}: {}",
+                                        self.guild_id.0, why
+                                    );
                                 }
                             }
+                            SetRepeatMode(repeat_mode, tx) => {
+                                self.repeat_mode = repeat_mode;
+                                self.reply(tx, (), "acknowledgement");
+                            }
                         }
                     }
 
-                    TrackFinished(should_continue) => {
-                        self.last_should_continue = should_continue;
+                    TrackFinished(should_continue) => self.track_finished(should_continue),
+                    TrackEnded(reason, track) => {
+                        let should_continue: bool = reason.clone().into();
+                        self.ended_track = should_continue.then_some(track);
 
-                        if should_continue {
-                            if let Err(why) = self.dummy.skip() {
-                                error!(
-                                    "Error sending skip message in player {}: {}",
-                                    self.guild_id.0, why
-                                );
+                        if reason == events::TrackEndReason::Finished
+                            && self.repeat_mode == super::RepeatMode::Track
+                        {
+                            if let Some(track) = self.current_track.take() {
+                                self.push_front(track);
                             }
                         }
+
+                        self.track_finished(should_continue);
                     }
                     StartTrack => {
-                        if let Some(track) = self.queue.pop_front() {
-                            if let Err(why) = self
-                                .dummy
-                                .update_player(&track.into_update_player(), false)
-                                .await
-                            {
-                                error!(
+                        let volume = self.volume_target();
+                        let interrupted = self.stop_fade();
+                        let finished_track = self.current_track.take();
+                        let ended_track = self.ended_track.take();
+
+                        if let Some(track) = finished_track.clone() {
+                            if self.repeat_mode == super::RepeatMode::Queue {
+                                self.push_back(track.clone());
+                            }
+
+                            if self.history.len() >= super::HISTORY_LIMIT {
+                                self.history.pop_front();
+                            }
+                            self.history.push_back(track);
+                        }
+
+                        self.current_track = self.queue.pop_front();
+
+                        if let Some(track) = &self.current_track {
+                            self.queue_changed(super::QueueChange::Removed {
+                                index: 0,
+                                tracks: vec![track.clone()],
+                            });
+                        }
+
+                        if let Some(track) = self.current_track.clone() {
+                            let faded = self.restore_volume.take().is_some() || interrupted;
+                            let fade_in = track.fade_in;
+                            let start_time = track.start_time.unwrap_or_default();
+                            let target = match &track.filters {
+                                Some(filters) => filters.volume,
+                                None => volume,
+                            };
+
+                            let mut update_player = track.into_update_player();
+
+                            if fade_in.is_some() || faded {
+                                let mut filters = update_player
+                                    .filters
+                                    .take()
+                                    .or_else(|| self.player_data.filters.clone())
+                                    .unwrap_or_default();
+
+                                filters.volume = if fade_in.is_some() { Some(0.0) } else { target };
+                                update_player.filters = Some(filters);
+                            }
+
+                            // Storing the player right away keeps `get_player` from returning
+                            // the previous track until the update sent back is handled.
+                            match self.dummy.update_player(&update_player, false).await {
+                                Ok(player) => {
+                                    self.track_started_at = player.state.time;
+                                    self.player_data = player;
+                                }
+                                Err(why) => error!(
                                     "Error sending update_player request in player {}: {}",
                                     self.guild_id.0, why
+                                ),
+                            }
+
+                            if let Some(duration) = fade_in {
+                                self.start_fade(
+                                    FadeKind::In,
+                                    Duration::ZERO,
+                                    target,
+                                    duration,
+                                    None,
                                 );
                             }
+
+                            self.schedule_fade_out(start_time);
                         } else {
+                            if interrupted && self.restore_volume.is_none() {
+                                self.restore_volume = Some(volume);
+                            }
+
+                            // Tracks played with `play_now` end the queue too, even though
+                            // they were never in it.
+                            if let Some(track) = finished_track.map(|x| x.track).or(ended_track) {
+                                self.dispatch(
+                                    events::QueueEnded {
+                                        guild_id: self.guild_id,
+                                        track,
+                                    },
+                                    |e| e.queue_ended,
+                                );
+                            }
+
                             if let Err(why) = self.dummy.stop_now().await {
                                 error!(
                                     "Error sending stop request in player {}: {}",
@@ -125,10 +493,328 @@ impl PlayerContextInner {
                             }
                         }
                     }
-                    Close => rx.close(),
+                    Previous(tx) => {
+                        let track = self.history.pop_back();
+
+                        if let Some(track) = &track {
+                            // Taking the current track keeps it out of the history.
+                            if let Some(current_track) = self.current_track.take() {
+                                self.push_front(current_track);
+                            }
+
+                            self.push_front(track.clone());
+
+                            if let Err(why) = self.dummy.skip() {
+                                error!(
+                                    "Error sending skip message in player {}: {}",
+                                    self.guild_id.0, why
+                                );
+                            }
+                        }
+
+                        if let Err(why) = tx.send(track) {
+                            error!(
+                                "Error sending previous track back to the player {}: {}",
+                                self.guild_id.0, why
+                            );
+                        }
+                    }
+                    FadeTo(volume, duration, tx) => {
+                        self.restore_volume = None;
+                        self.start_fade(
+                            FadeKind::Manual,
+                            Duration::ZERO,
+                            Some(volume),
+                            duration,
+                            Some(tx),
+                        );
+                    }
+                    FadeOut(duration, tx) => {
+                        if self.restore_volume.is_none() {
+                            self.restore_volume = Some(self.volume_target());
+                        }
+
+                        self.start_fade(
+                            FadeKind::Manual,
+                            Duration::ZERO,
+                            Some(0.0),
+                            duration,
+                            Some(tx),
+                        );
+                    }
+                    Close => {
+                        self.stop_fade();
+                        rx.close()
+                    }
+                };
+            }
+        });
+    }
+
+    fn track_finished(&mut self, should_continue: bool) {
+        self.last_should_continue = should_continue;
+
+        if should_continue {
+            if let Err(why) = self.dummy.skip() {
+                error!(
+                    "Error sending skip message in player {}: {}",
+                    self.guild_id.0, why
+                );
+            }
+        }
+    }
+
+    fn track_queued<'a>(
+        &self,
+        index: usize,
+        tracks: impl IntoIterator<Item = &'a super::TrackInQueue>,
+    ) {
+        self.dispatch(
+            events::TrackQueued {
+                guild_id: self.guild_id,
+                index,
+                tracks: tracks.into_iter().map(|x| x.track.clone()).collect(),
+            },
+            |e| e.track_queued,
+        );
+    }
+
+    /// Dispatch an event without waiting for the hooks, as they may use this player.
+    fn dispatch<T, F>(&self, event: T, handler: F)
+    where
+        F: Fn(&events::Events) -> Option<fn(LavalinkClient, String, &T) -> BoxFuture<()>>
+            + Send
+            + 'static,
+        T: Clone + Into<events::LavalinkEvent> + Send + Sync + 'static,
+    {
+        let event: QueuedEvent = Box::new(move |node, client| {
+            Box::pin(async move {
+                crate::node::EventDispatcher(&node, &client)
+                    .dispatch(event, handler)
+                    .await;
+            })
+        });
+
+        if self.event_tx.send(event).is_err() {
+            error!(
+                "Error queueing an event of the player {}: the event task stopped",
+                self.guild_id.0
+            );
+        }
+    }
+
+    /// Merge a change into the current filters and send them to the node.
+    ///
+    /// The player is stored right away instead of through `UpdatePlayer`, so the next update is
+    /// made on top of this one.
+    async fn update_filters(
+        &mut self,
+        update: super::FiltersUpdate,
+    ) -> LavalinkResult<player::Player> {
+        let filters = self.player_data.filters.clone().unwrap_or_default();
+        let filters = update(filters.into()).build()?;
+
+        let node = self.dummy.client.get_node_for_guild(self.guild_id).await;
+
+        let player = node
+            .http
+            .update_player(
+                self.guild_id,
+                &node.session_id.load(),
+                &http::UpdatePlayer {
+                    filters: Some(filters),
+                    ..Default::default()
+                },
+                true,
+            )
+            .await?;
+
+        self.player_data = player.clone();
+
+        Ok(player)
+    }
+
+    /// The volume filter the player is at, or is heading to while fading in or out.
+    fn volume_target(&self) -> Option<f64> {
+        if let Some(volume) = self.restore_volume {
+            return volume;
+        }
+
+        match &self.fade {
+            Some(fade) if !fade.handle.is_finished() => fade.target,
+            _ => self.player_data.filters.as_ref().and_then(|x| x.volume),
+        }
+    }
+
+    /// Abort the current fade, returning whether it was still running.
+    fn stop_fade(&mut self) -> bool {
+        match self.fade.take() {
+            Some(fade) => {
+                let running = !fade.handle.is_finished();
+                fade.handle.abort();
+                running
+            }
+            None => false,
+        }
+    }
+
+    /// Replace the current fade with a ramp of the volume filter, starting after the delay.
+    fn start_fade(
+        &mut self,
+        kind: FadeKind,
+        delay: Duration,
+        target: Option<f64>,
+        duration: Duration,
+        done: Option<oneshot::Sender<()>>,
+    ) {
+        self.stop_fade();
+
+        let player = self.dummy.clone();
+
+        let handle = tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+
+            let Ok(current) = player.get_player().await else {
+                return;
+            };
+
+            let from = current.filters.and_then(|x| x.volume).unwrap_or(1.0);
+            let to = target.unwrap_or(1.0);
+            let steps = (duration.as_millis() / super::FADE_STEP.as_millis()).max(1) as u32;
+
+            for step in 1..=steps {
+                tokio::time::sleep(duration / steps).await;
+
+                let volume = if step == steps {
+                    to
+                } else {
+                    from + (to - from) * step as f64 / steps as f64
                 };
+
+                if let Err(why) = player.update_filters(move |x| x.volume(volume)).await {
+                    error!(
+                        "Error fading the volume in player {}: {}",
+                        player.guild_id.0, why
+                    );
+                    return;
+                }
+            }
+
+            if let Some(done) = done {
+                let _ = done.send(());
+            }
+        });
+
+        self.fade = Some(Fade {
+            kind,
+            target,
+            handle,
+        });
+    }
+
+    /// Schedule the fade out of the current track from its position, replacing the one that was
+    /// scheduled with an older position.
+    fn schedule_fade_out(&mut self, position: Duration) {
+        let Some(track) = &self.current_track else {
+            return;
+        };
+        let Some(fade_out) = track.fade_out else {
+            return;
+        };
+
+        let end = match track.end_time {
+            Some(end_time) => end_time,
+            None if !track.track.info.is_stream => Duration::from_millis(track.track.info.length),
+            None => return,
+        };
+
+        let remaining = end.saturating_sub(position);
+        let delay = remaining.saturating_sub(fade_out);
+
+        if let Some(fade) = &self.fade {
+            if !fade.handle.is_finished() {
+                match fade.kind {
+                    FadeKind::Manual => return,
+                    // The fade out is scheduled by a later update once the fade in is over.
+                    FadeKind::In if !delay.is_zero() => return,
+                    _ => {}
+                }
             }
+        }
+
+        if self.player_data.paused {
+            if self.fade.as_ref().is_some_and(|x| x.kind == FadeKind::Out) {
+                self.stop_fade();
+            }
+
+            return;
+        }
+
+        if self.restore_volume.is_none() {
+            self.restore_volume = Some(self.volume_target());
+        }
+
+        self.start_fade(
+            FadeKind::Out,
+            delay,
+            Some(0.0),
+            remaining.min(fade_out),
+            None,
+        );
+    }
+
+    fn push_back(&mut self, track: super::TrackInQueue) {
+        self.queue_changed(super::QueueChange::Added {
+            index: self.queue.len(),
+            tracks: vec![track.clone()],
+        });
+        self.queue.push_back(track);
+    }
+
+    fn push_front(&mut self, track: super::TrackInQueue) {
+        self.queue_changed(super::QueueChange::Added {
+            index: 0,
+            tracks: vec![track.clone()],
         });
+        self.queue.push_front(track);
+    }
+
+    fn snapshot(&self) -> super::QueueSnapshot {
+        super::QueueSnapshot {
+            revision: self.queue_revision,
+            tracks: self.queue.clone(),
+        }
+    }
+
+    /// Bump the revision of the queue and notify the subscribers of the change.
+    fn queue_changed(&mut self, change: super::QueueChange) {
+        self.queue_revision += 1;
+
+        // Having no subscribers is not an error.
+        let _ = self.queue_tx.send(super::QueueUpdate {
+            revision: self.queue_revision,
+            change,
+        });
+    }
+
+    fn check_index(&self, index: usize, bound: usize) -> LavalinkResult<()> {
+        if index < bound {
+            Ok(())
+        } else {
+            Err(LavalinkError::IndexOutOfBounds {
+                index,
+                len: self.queue.len(),
+            })
+        }
+    }
+
+    fn reply<T>(&self, tx: oneshot::Sender<T>, value: T, what: &str) {
+        if let Err(why) = tx.send(value) {
+            error!(
+                "Error sending {} back to the player {}: {}",
+                what, self.guild_id.0, why
+            );
+        }
     }
 
     async fn queue_init(&self) {
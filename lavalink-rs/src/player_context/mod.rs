@@ -1,14 +1,26 @@
 mod context;
 mod inner;
 
+use crate::error::LavalinkResult;
 use crate::model::*;
 
 use std::collections::VecDeque;
 
+use tokio::sync::broadcast;
+
 pub use context::PlayerContext;
 pub use context::QueueRef;
 pub(crate) use inner::PlayerContextInner;
 
+/// How many played tracks the history of a player keeps, dropping the oldest ones.
+pub const HISTORY_LIMIT: usize = 50;
+
+/// How often the volume filter is updated while fading, every step being a request to the node.
+pub const FADE_STEP: std::time::Duration = std::time::Duration::from_millis(250);
+
+/// How many queue updates a subscriber can fall behind before it starts over from a snapshot.
+pub(crate) const QUEUE_CHANNEL_CAPACITY: usize = 256;
+
 #[derive(PartialEq, Debug, Clone, Default)]
 #[cfg_attr(feature = "python", pyo3::pyclass)]
 /// A track that's inside the queue.
@@ -23,18 +35,102 @@ pub struct TrackInQueue {
     pub volume: Option<u16>,
     /// The filters the track should start at.
     pub filters: Option<player::Filters>,
+    /// How long the volume filter takes to ramp up from silence when the track starts.
+    pub fade_in: Option<std::time::Duration>,
+    /// How long the volume filter takes to ramp down to silence before the track ends, either at
+    /// `end_time` or naturally.
+    ///
+    /// Streams without an `end_time` don't fade out.
+    pub fade_out: Option<std::time::Duration>,
+}
+
+#[derive(PartialEq, Eq, Hash, Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "python", pyo3::pyclass)]
+/// What to play once the current track finishes.
+pub enum RepeatMode {
+    /// Play the next track in the queue.
+    #[default]
+    Off,
+    /// Play the current track again.
+    ///
+    /// Skipping still moves on to the next track in the queue.
+    Track,
+    /// Add the current track to the end of the queue when moving on to the next one, including
+    /// when skipping it.
+    Queue,
 }
 
+#[derive(PartialEq, Debug, Clone, Default)]
+/// The entire queue at a specific revision.
+pub struct QueueSnapshot {
+    /// How many changes were made to the queue before this snapshot.
+    pub revision: u64,
+    pub tracks: VecDeque<TrackInQueue>,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+/// A change made to the queue.
+pub struct QueueUpdate {
+    /// The revision of the queue after this change.
+    ///
+    /// Every change increases it by one, so a gap means some updates were missed.
+    pub revision: u64,
+    pub change: QueueChange,
+}
+
+#[derive(PartialEq, Debug, Clone)]
+/// The different ways the queue can change.
+///
+/// The indexes refer to the queue as it was right before the change, so applying the changes
+/// in order to a copy of the queue keeps it up to date.
+pub enum QueueChange {
+    /// The tracks were inserted, the first one ending up at the index.
+    Added {
+        index: usize,
+        tracks: Vec<TrackInQueue>,
+    },
+    /// The tracks starting at the index were removed.
+    Removed {
+        index: usize,
+        tracks: Vec<TrackInQueue>,
+    },
+    /// The track at `from` was moved to `to`.
+    Moved { from: usize, to: usize },
+    /// The tracks at the two indexes traded places.
+    Swapped { first: usize, second: usize },
+    /// The track at the index was replaced with a new one.
+    Replaced {
+        index: usize,
+        track: Box<TrackInQueue>,
+    },
+    /// Every track was removed.
+    Cleared,
+    /// The queue changed entirely, like when it's replaced or shuffled, and this is all of it.
+    Reset { tracks: VecDeque<TrackInQueue> },
+}
+
+/// A change of the filters of a player, made on top of the ones it has when the change is applied.
+pub(crate) type FiltersUpdate =
+    Box<dyn FnOnce(player::FiltersBuilder) -> player::FiltersBuilder + Send>;
+
 pub(crate) enum PlayerMessage {
     GetPlayer(oneshot::Sender<player::Player>),
     UpdatePlayer(player::Player),
     UpdatePlayerTrack(Option<track::TrackData>),
     UpdatePlayerState(player::State),
+    UpdateFilters(
+        FiltersUpdate,
+        oneshot::Sender<LavalinkResult<player::Player>>,
+    ),
 
     QueueMessage(QueueMessage),
 
     TrackFinished(bool),
+    TrackEnded(events::TrackEndReason, track::TrackData),
     StartTrack,
+    Previous(oneshot::Sender<Option<TrackInQueue>>),
+    FadeTo(f64, std::time::Duration, oneshot::Sender<()>),
+    FadeOut(std::time::Duration, oneshot::Sender<()>),
     Close,
 }
 
@@ -42,29 +138,91 @@ pub(crate) enum PlayerMessage {
 pub enum QueueMessage {
     /// Clone the entire queue and return it.
     GetQueue(oneshot::Sender<VecDeque<TrackInQueue>>),
+    /// Clone the entire queue along with its revision and return it.
+    GetSnapshot(oneshot::Sender<QueueSnapshot>),
+    /// Subscribe to the changes made after the returned snapshot.
+    Subscribe(oneshot::Sender<(QueueSnapshot, broadcast::Receiver<QueueUpdate>)>),
     /// Get the track at a specific index.
     GetTrack(usize, oneshot::Sender<Option<TrackInQueue>>),
     /// Get the length of the queue
     GetCount(oneshot::Sender<usize>),
+    /// Clone the tracks that already played and return them, from oldest to newest.
+    GetHistory(oneshot::Sender<VecDeque<TrackInQueue>>),
     /// Add a track to the end of the queue.
-    PushToBack(TrackInQueue),
+    PushToBack(TrackInQueue, oneshot::Sender<()>),
     /// Add a track to the start of the queue.
-    PushToFront(TrackInQueue),
+    PushToFront(TrackInQueue, oneshot::Sender<()>),
     /// Insert a track to a specific position in the queue.
-    Insert(usize, TrackInQueue),
-    /// Remove a track from the queue.
-    Remove(usize),
+    Insert(usize, TrackInQueue, oneshot::Sender<LavalinkResult<()>>),
+    /// Remove a track from the queue and return it.
+    Remove(usize, oneshot::Sender<LavalinkResult<TrackInQueue>>),
+    /// Remove the tracks in the range from the queue and return them.
+    RemoveRange(
+        (std::ops::Bound<usize>, std::ops::Bound<usize>),
+        oneshot::Sender<LavalinkResult<VecDeque<TrackInQueue>>>,
+    ),
+    /// Move a track from one position in the queue to another.
+    Move(usize, usize, oneshot::Sender<LavalinkResult<()>>),
+    /// Remove the tracks the predicate returns false for, and return them.
+    Retain(TrackPredicate, oneshot::Sender<VecDeque<TrackInQueue>>),
     /// Clear the queue.
-    Clear,
+    Clear(oneshot::Sender<()>),
     /// Replace the entire queue with another one.
-    Replace(VecDeque<TrackInQueue>),
+    Replace(VecDeque<TrackInQueue>, oneshot::Sender<()>),
     /// Append a queue to the end of the current one.
-    Append(VecDeque<TrackInQueue>),
-    /// Swap the track at the index with the new track.
-    Swap(usize, TrackInQueue),
+    Append(VecDeque<TrackInQueue>, oneshot::Sender<()>),
+    /// Swap the track at the index with the new track, and return the old one.
+    Swap(
+        usize,
+        TrackInQueue,
+        oneshot::Sender<LavalinkResult<TrackInQueue>>,
+    ),
+    /// Exchange the positions of the tracks at the two indexes.
+    SwapIndices(usize, usize, oneshot::Sender<LavalinkResult<()>>),
+    /// Shuffle the tracks in the queue.
+    Shuffle(oneshot::Sender<()>),
+    /// Get the repeat mode of the player.
+    GetRepeatMode(oneshot::Sender<RepeatMode>),
+    /// Set the repeat mode of the player.
+    SetRepeatMode(RepeatMode, oneshot::Sender<()>),
+}
+
+impl QueueMessage {
+    /// Whether the message only reads the queue, without changing it.
+    pub(crate) fn is_read_only(&self) -> bool {
+        matches!(
+            self,
+            QueueMessage::GetQueue(_)
+                | QueueMessage::GetSnapshot(_)
+                | QueueMessage::Subscribe(_)
+                | QueueMessage::GetTrack(..)
+                | QueueMessage::GetCount(_)
+                | QueueMessage::GetHistory(_)
+                | QueueMessage::GetRepeatMode(_)
+        )
+    }
+}
+
+/// A predicate run on the tracks of the queue inside the player.
+pub struct TrackPredicate(pub Box<dyn FnMut(&TrackInQueue) -> bool + Send>);
+
+impl std::fmt::Debug for TrackPredicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("TrackPredicate")
+    }
 }
 
 impl TrackInQueue {
+    /// Set the user data of the track, which is sent to Lavalink when it is played.
+    ///
+    /// Read it back with `TrackData::user_data_as`, from the queue, the track events or the
+    /// player.
+    pub fn with_user_data<T: serde::Serialize>(mut self, user_data: &T) -> LavalinkResult<Self> {
+        self.track.set_user_data(user_data)?;
+
+        Ok(self)
+    }
+
     fn into_update_player(self) -> http::UpdatePlayer {
         http::UpdatePlayer {
             track: Some(http::UpdatePlayerTrack {
@@ -1,6 +1,8 @@
 pub use crate::client::LavalinkClient;
 pub use crate::error::LavalinkResult;
+pub use crate::model::client::ClientOptions;
 pub use crate::model::client::NodeDistributionStrategy;
+pub use crate::model::events::LavalinkEvent;
 pub use crate::model::search::SearchEngines;
 pub use crate::model::track::TrackLoadData;
 pub use crate::model::GuildId;
@@ -8,4 +10,5 @@ pub use crate::model::UserId;
 pub use crate::node::NodeBuilder;
 pub use crate::player_context::PlayerContext;
 pub use crate::player_context::QueueMessage;
+pub use crate::player_context::RepeatMode;
 pub use crate::player_context::TrackInQueue;
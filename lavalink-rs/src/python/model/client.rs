@@ -61,6 +61,20 @@ impl NodeDistributionStrategyPy {
         }
     }
 
+    #[staticmethod]
+    pub fn lowest_penalty() -> Self {
+        Self {
+            inner: NodeDistributionStrategy::lowest_penalty(),
+        }
+    }
+
+    #[staticmethod]
+    pub fn by_region() -> Self {
+        Self {
+            inner: NodeDistributionStrategy::by_region(),
+        }
+    }
+
     #[staticmethod]
     pub fn custom(func: PyObject) -> Self {
         Self {
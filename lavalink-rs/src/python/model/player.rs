@@ -18,6 +18,7 @@ pub fn player(py: Python<'_>, m: &PyModule) -> PyResult<()> {
     player.add_class::<Rotation>()?;
     player.add_class::<Timescale>()?;
     player.add_class::<TremoloVibrato>()?;
+    player.add_class::<BassBoost>()?;
 
     m.add_submodule(player)?;
 
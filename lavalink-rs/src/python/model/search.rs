@@ -8,6 +8,7 @@ pub fn search(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<SearchEngines>()?;
     m.add_class::<SpotifyRecommendedParameters>()?;
     m.add_class::<FloweryTTSParameters>()?;
+    m.add_class::<SearchType>()?;
 
     Ok(())
 }
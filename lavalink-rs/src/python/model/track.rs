@@ -13,6 +13,9 @@ pub fn track(_py: Python<'_>, m: &PyModule) -> PyResult<()> {
     m.add_class::<PlaylistData>()?;
     m.add_class::<PlaylistInfo>()?;
     m.add_class::<TrackError>()?;
+    m.add_class::<crate::model::lavasrc::LavaSrcTrackInfo>()?;
+    m.add_class::<crate::model::lavasrc::LavaSrcPlaylistInfo>()?;
+    m.add_class::<crate::model::lavasrc::LavaSrcPlaylistType>()?;
 
     Ok(())
 }
@@ -47,6 +50,11 @@ impl TrackData {
     fn set_user_data(&mut self, py: Python<'_>, input: PyObject) {
         self.user_data = depythonize(input.as_ref(py)).unwrap()
     }
+
+    #[getter(lavasrc_info)]
+    fn get_lavasrc_info(&self) -> Option<crate::model::lavasrc::LavaSrcTrackInfo> {
+        self.lavasrc_info()
+    }
 }
 
 #[apply(crate::python::with_getter_setter)]
@@ -63,4 +71,9 @@ impl PlaylistData {
     fn set_plugin_info(&mut self, py: Python<'_>, input: PyObject) {
         self.plugin_info = depythonize(input.as_ref(py)).unwrap()
     }
+
+    #[getter(lavasrc_info)]
+    fn get_lavasrc_info(&self) -> Option<crate::model::lavasrc::LavaSrcPlaylistInfo> {
+        self.lavasrc_info()
+    }
 }
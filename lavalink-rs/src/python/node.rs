@@ -37,6 +37,7 @@ impl crate::node::NodeBuilder {
         (password, String),
         (user_id, crate::model::UserId),
         (session_id, Option<String>),
+        (regions, Vec<String>),
     );
 
     #[new]
@@ -73,6 +74,9 @@ impl crate::node::NodeBuilder {
             password,
             user_id: user_id.into(),
             session_id,
+            reconnect_policy: None,
+            request_policy: None,
+            regions: Vec::new(),
         })
     }
 }
@@ -129,6 +129,32 @@ impl crate::player_context::PlayerContext {
         })
     }
 
+    #[pyo3(name = "fade_to_ms")]
+    fn fade_to_ms_py<'a>(&self, py: Python<'a>, volume: u16, duration: u64) -> PyResult<&'a PyAny> {
+        let player = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            player
+                .fade_to(volume, Duration::from_millis(duration))
+                .await?;
+
+            Ok(Python::with_gil(|py| py.None()))
+        })
+    }
+
+    #[pyo3(name = "skip_with_fade_ms")]
+    fn skip_with_fade_ms_py<'a>(&self, py: Python<'a>, duration: u64) -> PyResult<&'a PyAny> {
+        let player = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            player
+                .skip_with_fade(Duration::from_millis(duration))
+                .await?;
+
+            Ok(Python::with_gil(|py| py.None()))
+        })
+    }
+
     #[pyo3(name = "set_position_ms")]
     fn set_position_ms_py<'a>(&self, py: Python<'a>, position: u64) -> PyResult<&'a PyAny> {
         let player = self.clone();
@@ -141,9 +167,14 @@ impl crate::player_context::PlayerContext {
     }
 
     #[pyo3(name = "queue")]
-    fn queue_py<'a>(&self, track: PyTrackInQueue) -> PyResult<()> {
-        self.queue(track)?;
-        Ok(())
+    fn queue_py<'a>(&self, py: Python<'a>, track: PyTrackInQueue) -> PyResult<&'a PyAny> {
+        let player = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            player.queue(TrackInQueue::from(track)).await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "close")]
@@ -227,43 +258,138 @@ impl crate::player_context::QueueRef {
     }
 
     #[pyo3(name = "push_to_back")]
-    fn push_to_back_py(&self, track: PyTrackInQueue) -> PyResult<()> {
-        Ok(self.push_to_back(TrackInQueue::from(track))?)
+    fn push_to_back_py<'a>(&self, py: Python<'a>, track: PyTrackInQueue) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue.push_to_back(TrackInQueue::from(track)).await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "push_to_front")]
-    fn push_to_front_py(&self, track: PyTrackInQueue) -> PyResult<()> {
-        Ok(self.push_to_front(TrackInQueue::from(track))?)
+    fn push_to_front_py<'a>(&self, py: Python<'a>, track: PyTrackInQueue) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue.push_to_front(TrackInQueue::from(track)).await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "insert")]
-    fn insert_py(&self, index: usize, track: PyTrackInQueue) -> PyResult<()> {
-        Ok(self.insert(index, TrackInQueue::from(track))?)
+    fn insert_py<'a>(
+        &self,
+        py: Python<'a>,
+        index: usize,
+        track: PyTrackInQueue,
+    ) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue.insert(index, TrackInQueue::from(track)).await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "remove")]
-    fn remove_py(&self, index: usize) -> PyResult<()> {
-        Ok(self.remove(index)?)
+    fn remove_py<'a>(&self, py: Python<'a>, index: usize) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let q = queue.remove(index).await?;
+
+            Ok(Python::with_gil(|_py| q))
+        })
+    }
+
+    #[pyo3(name = "remove_range")]
+    fn remove_range_py<'a>(&self, py: Python<'a>, start: usize, end: usize) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let q: Vec<_> = queue.remove_range(start..end).await?.into();
+
+            Ok(Python::with_gil(|_py| q))
+        })
+    }
+
+    #[pyo3(name = "move_track")]
+    fn move_track_py<'a>(&self, py: Python<'a>, from: usize, to: usize) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue.move_track(from, to).await?;
+
+            Ok(())
+        })
+    }
+
+    #[pyo3(name = "dedupe_by_identifier")]
+    fn dedupe_by_identifier_py<'a>(&self, py: Python<'a>) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let q: Vec<_> = queue.dedupe_by_identifier().await?.into();
+
+            Ok(Python::with_gil(|_py| q))
+        })
     }
 
     #[pyo3(name = "clear")]
-    fn clear_py(&self) -> PyResult<()> {
-        Ok(self.clear()?)
+    fn clear_py<'a>(&self, py: Python<'a>) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue.clear().await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "replace")]
-    fn replace_py(&self, tracks: Vec<PyTrackInQueue>) -> PyResult<()> {
-        Ok(self.replace(tracks.into_iter().map(TrackInQueue::from).collect())?)
+    fn replace_py<'a>(&self, py: Python<'a>, tracks: Vec<PyTrackInQueue>) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue
+                .replace(tracks.into_iter().map(TrackInQueue::from).collect())
+                .await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "append")]
-    fn append_py(&self, tracks: Vec<PyTrackInQueue>) -> PyResult<()> {
-        Ok(self.append(tracks.into_iter().map(TrackInQueue::from).collect())?)
+    fn append_py<'a>(&self, py: Python<'a>, tracks: Vec<PyTrackInQueue>) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            queue
+                .append(tracks.into_iter().map(TrackInQueue::from).collect())
+                .await?;
+
+            Ok(())
+        })
     }
 
     #[pyo3(name = "swap")]
-    fn swap_py(&self, index: usize, track: PyTrackInQueue) -> PyResult<()> {
-        Ok(self.swap(index, TrackInQueue::from(track))?)
+    fn swap_py<'a>(
+        &self,
+        py: Python<'a>,
+        index: usize,
+        track: PyTrackInQueue,
+    ) -> PyResult<&'a PyAny> {
+        let queue = self.clone();
+
+        pyo3_asyncio::tokio::future_into_py(py, async move {
+            let q = queue.swap(index, TrackInQueue::from(track)).await?;
+
+            Ok(Python::with_gil(|_py| q))
+        })
     }
 
     // TODO: pyo3 0.21
@@ -306,6 +432,28 @@ impl crate::player_context::TrackInQueue {
         self.end_time = ms.map(|ms| Duration::from_millis(ms));
         Ok(())
     }
+
+    #[getter]
+    fn get_fade_in_ms(&self) -> Option<u128> {
+        self.fade_in.map(|ms| ms.as_millis())
+    }
+
+    #[setter]
+    fn set_fade_in_ms(&mut self, ms: Option<u64>) -> pyo3::PyResult<()> {
+        self.fade_in = ms.map(|ms| Duration::from_millis(ms));
+        Ok(())
+    }
+
+    #[getter]
+    fn get_fade_out_ms(&self) -> Option<u128> {
+        self.fade_out.map(|ms| ms.as_millis())
+    }
+
+    #[setter]
+    fn set_fade_out_ms(&mut self, ms: Option<u64>) -> pyo3::PyResult<()> {
+        self.fade_out = ms.map(|ms| Duration::from_millis(ms));
+        Ok(())
+    }
 }
 
 #[derive(FromPyObject)]
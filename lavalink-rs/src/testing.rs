@@ -0,0 +1,1373 @@
+use crate::error::{LavalinkResult, ResponseError};
+use crate::model::*;
+use crate::node::NodeBuilder;
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use ::http::{header, HeaderMap, Method, Request, Response, StatusCode};
+use bytes::Bytes;
+use futures::{SinkExt, StreamExt};
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper_util::rt::TokioIo;
+use tokio::net::TcpListener;
+use tokio::sync::{mpsc, watch};
+use tokio::task::AbortHandle;
+use tokio_tungstenite::tungstenite::handshake::derive_accept_key;
+use tokio_tungstenite::tungstenite::protocol::Role;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::WebSocketStream;
+
+/// The password the mock server expects by default.
+pub const DEFAULT_PASSWORD: &str = "youshallnotpass";
+
+/// A scriptable Lavalink server that runs inside the test process.
+///
+/// It speaks the v4 REST API and websocket protocol on a random local port, keeping the players
+/// it is sent in memory. Every request it receives is recorded, and events can be pushed to the
+/// connected client at any time.
+///
+/// The server only sends `ready` by itself, when a client connects. Everything else, like
+/// `TrackStartEvent` after a player starts a track, has to be sent by the test.
+///
+/// The server stops when dropped.
+///
+/// # Example
+///
+/// ```ignore
+/// let server = MockServer::start().await;
+/// let client = LavalinkClient::new(
+///     Default::default(),
+///     vec![server.node_builder(UserId(1))],
+///     NodeDistributionStrategy::new(),
+/// )
+/// .await;
+///
+/// server.wait_for_connections(1).await;
+/// server.send_track_start(GuildId(1), track);
+/// ```
+#[derive(Debug)]
+pub struct MockServer {
+    address: SocketAddr,
+    state: Arc<MockState>,
+    accept: AbortHandle,
+}
+
+#[derive(Debug)]
+struct MockState {
+    password: String,
+    auto_ready: AtomicBool,
+    session_id: Mutex<String>,
+    resuming: AtomicBool,
+    socket: Mutex<Option<mpsc::UnboundedSender<Message>>>,
+    players: Mutex<HashMap<GuildId, player::Player>>,
+    load_results: Mutex<HashMap<String, track::Track>>,
+    search_results: Mutex<HashMap<String, search::SearchResult>>,
+    route_planner: Mutex<Option<http::RoutePlannerStatus>>,
+    lyrics: Mutex<HashMap<String, lyrics::Lyrics>>,
+    lyrics_subscriptions: Mutex<HashSet<GuildId>>,
+    sponsorblock: Mutex<HashMap<GuildId, Vec<sponsorblock::SegmentCategory>>>,
+    responses: Mutex<Vec<ScriptedResponse>>,
+    requests: Mutex<Vec<ReceivedRequest>>,
+    request_count: watch::Sender<usize>,
+    connection_count: watch::Sender<usize>,
+    tasks: Mutex<Vec<AbortHandle>>,
+}
+
+#[derive(Debug, Clone)]
+/// A request received by the mock server, including websocket handshakes.
+pub struct ReceivedRequest {
+    pub method: Method,
+    /// The path of the request, without the query.
+    pub path: String,
+    /// The raw query of the request, if any.
+    pub query: Option<String>,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// A response returned by the mock server instead of the default one.
+pub enum MockResponse {
+    /// A JSON body with this status code.
+    Json(u16, serde_json::Value),
+    /// A plain text body with this status code.
+    Text(u16, String),
+    /// A Lavalink error body with this status code and message.
+    Error(u16, String),
+    /// The response, sent after waiting for some time.
+    Delayed(std::time::Duration, Box<MockResponse>),
+}
+
+#[derive(Debug)]
+struct ScriptedResponse {
+    method: Method,
+    path: String,
+    response: MockResponse,
+    once: bool,
+}
+
+impl MockServer {
+    /// Start a server that expects the default password.
+    pub async fn start() -> MockServer {
+        Self::start_with_password(DEFAULT_PASSWORD).await
+    }
+
+    /// Start a server that only accepts this password.
+    pub async fn start_with_password(password: &str) -> MockServer {
+        let listener = TcpListener::bind("127.0.0.1:0")
+            .await
+            .expect("Failed to bind the mock Lavalink server.");
+        let address = listener
+            .local_addr()
+            .expect("Failed to get the address of the mock Lavalink server.");
+
+        let state = Arc::new(MockState {
+            password: password.to_string(),
+            auto_ready: AtomicBool::new(true),
+            session_id: Mutex::new(new_session_id()),
+            resuming: AtomicBool::new(false),
+            socket: Mutex::new(None),
+            players: Mutex::new(HashMap::new()),
+            load_results: Mutex::new(HashMap::new()),
+            search_results: Mutex::new(HashMap::new()),
+            route_planner: Mutex::new(None),
+            lyrics: Mutex::new(HashMap::new()),
+            lyrics_subscriptions: Mutex::new(HashSet::new()),
+            sponsorblock: Mutex::new(HashMap::new()),
+            responses: Mutex::new(vec![]),
+            requests: Mutex::new(vec![]),
+            request_count: watch::channel(0).0,
+            connection_count: watch::channel(0).0,
+            tasks: Mutex::new(vec![]),
+        });
+
+        let accept = tokio::spawn({
+            let state = state.clone();
+
+            async move {
+                loop {
+                    let stream = match listener.accept().await {
+                        Ok((stream, _)) => stream,
+                        Err(why) => {
+                            warn!(
+                                "Mock Lavalink server failed to accept a connection: {}",
+                                why
+                            );
+                            continue;
+                        }
+                    };
+
+                    let connection = tokio::spawn({
+                        let state = state.clone();
+
+                        async move {
+                            let service = hyper::service::service_fn(move |request| {
+                                handle_request(state.clone(), request)
+                            });
+
+                            if let Err(why) = hyper::server::conn::http1::Builder::new()
+                                .serve_connection(TokioIo::new(stream), service)
+                                .with_upgrades()
+                                .await
+                            {
+                                debug!("Mock Lavalink server connection closed: {}", why);
+                            }
+                        }
+                    });
+
+                    state.tasks.lock().unwrap().push(connection.abort_handle());
+                }
+            }
+        });
+
+        MockServer {
+            address,
+            state,
+            accept: accept.abort_handle(),
+        }
+    }
+
+    /// The hostname to connect to, like "127.0.0.1:43210".
+    pub fn hostname(&self) -> String {
+        self.address.to_string()
+    }
+
+    /// A node builder that connects to this server.
+    pub fn node_builder(&self, user_id: impl Into<UserId>) -> NodeBuilder {
+        NodeBuilder {
+            hostname: self.hostname(),
+            is_ssl: false,
+            events: Default::default(),
+            password: self.state.password.clone(),
+            user_id: user_id.into(),
+            session_id: None,
+            reconnect_policy: None,
+            request_policy: None,
+            regions: Vec::new(),
+        }
+    }
+
+    /// The ID of the current session.
+    pub fn session_id(&self) -> String {
+        self.state.session_id.lock().unwrap().clone()
+    }
+
+    /// Whether to send `ready` as soon as a client connects, which is the default.
+    pub fn set_auto_ready(&self, auto_ready: bool) {
+        self.state.auto_ready.store(auto_ready, Ordering::SeqCst);
+    }
+
+    /// Whether a client is currently connected to the websocket.
+    pub fn is_connected(&self) -> bool {
+        self.state
+            .socket
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|x| !x.is_closed())
+    }
+
+    /// The amount of websocket connections accepted since the server started.
+    pub fn connection_count(&self) -> usize {
+        *self.state.connection_count.borrow()
+    }
+
+    /// Wait until the server accepted at least this amount of websocket connections.
+    pub async fn wait_for_connections(&self, count: usize) {
+        let mut rx = self.state.connection_count.subscribe();
+        let _ = rx.wait_for(|x| *x >= count).await;
+    }
+
+    /// Close the websocket connection, as if the server went down.
+    ///
+    /// Unless resuming was enabled for the session, the players are destroyed once the client
+    /// reconnects.
+    pub fn disconnect(&self) {
+        if let Some(socket) = self.state.socket.lock().unwrap().take() {
+            let _ = socket.send(Message::Close(None));
+        }
+    }
+
+    /// Send a raw message to the connected client.
+    ///
+    /// Returns false if no client is connected.
+    pub fn send(&self, message: serde_json::Value) -> bool {
+        self.state.send(message)
+    }
+
+    /// Send `ready` with the current session ID.
+    pub fn send_ready(&self, resumed: bool) -> bool {
+        self.send(serde_json::json!({
+            "op": "ready",
+            "resumed": resumed,
+            "sessionId": self.session_id(),
+        }))
+    }
+
+    /// Send `playerUpdate` with this state.
+    ///
+    /// The state of the stored player is updated too.
+    pub fn send_player_update(&self, guild_id: impl Into<GuildId>, state: player::State) -> bool {
+        let guild_id = guild_id.into();
+
+        if let Some(player) = self.state.players.lock().unwrap().get_mut(&guild_id) {
+            player.state = state.clone();
+        }
+
+        self.send(serde_json::json!({
+            "op": "playerUpdate",
+            "guildId": guild_id.0.to_string(),
+            "state": state,
+        }))
+    }
+
+    /// Send `stats`.
+    pub fn send_stats(&self, stats: &events::Stats) -> bool {
+        let mut message = serde_json::to_value(stats).unwrap();
+        message["op"] = "stats".into();
+
+        self.send(message)
+    }
+
+    /// Send `TrackStartEvent`.
+    pub fn send_track_start(&self, guild_id: impl Into<GuildId>, track: &track::TrackData) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "TrackStartEvent",
+            serde_json::json!({ "track": track }),
+        )
+    }
+
+    /// Send `TrackEndEvent`.
+    ///
+    /// The track of the stored player is cleared too.
+    pub fn send_track_end(
+        &self,
+        guild_id: impl Into<GuildId>,
+        track: &track::TrackData,
+        reason: events::TrackEndReason,
+    ) -> bool {
+        let guild_id = guild_id.into();
+
+        if let Some(player) = self.state.players.lock().unwrap().get_mut(&guild_id) {
+            player.track = None;
+        }
+
+        self.send_event(
+            guild_id,
+            "TrackEndEvent",
+            serde_json::json!({ "track": track, "reason": reason }),
+        )
+    }
+
+    /// Send `TrackExceptionEvent`.
+    pub fn send_track_exception(
+        &self,
+        guild_id: impl Into<GuildId>,
+        track: &track::TrackData,
+        exception: &track::TrackError,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "TrackExceptionEvent",
+            serde_json::json!({ "track": track, "exception": exception }),
+        )
+    }
+
+    /// Send `TrackStuckEvent`.
+    pub fn send_track_stuck(
+        &self,
+        guild_id: impl Into<GuildId>,
+        track: &track::TrackData,
+        threshold_ms: u64,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "TrackStuckEvent",
+            serde_json::json!({ "track": track, "thresholdMs": threshold_ms }),
+        )
+    }
+
+    /// Send `WebSocketClosedEvent`.
+    pub fn send_websocket_closed(
+        &self,
+        guild_id: impl Into<GuildId>,
+        code: u16,
+        reason: &str,
+        by_remote: bool,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "WebSocketClosedEvent",
+            serde_json::json!({ "code": code, "reason": reason, "byRemote": by_remote }),
+        )
+    }
+
+    /// Send `LyricsFoundEvent`.
+    pub fn send_lyrics_found(&self, guild_id: impl Into<GuildId>, lyrics: &lyrics::Lyrics) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "LyricsFoundEvent",
+            serde_json::json!({ "lyrics": lyrics }),
+        )
+    }
+
+    /// Send `LyricsNotFoundEvent`.
+    pub fn send_lyrics_not_found(&self, guild_id: impl Into<GuildId>) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "LyricsNotFoundEvent",
+            serde_json::json!({}),
+        )
+    }
+
+    /// Send `LyricsLineEvent`.
+    pub fn send_lyrics_line(
+        &self,
+        guild_id: impl Into<GuildId>,
+        line_index: usize,
+        line: &lyrics::LyricsLine,
+        skipped: bool,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "LyricsLineEvent",
+            serde_json::json!({ "lineIndex": line_index, "line": line, "skipped": skipped }),
+        )
+    }
+
+    /// Send `SegmentsLoaded`.
+    pub fn send_segments_loaded(
+        &self,
+        guild_id: impl Into<GuildId>,
+        segments: &[sponsorblock::Segment],
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "SegmentsLoaded",
+            serde_json::json!({ "segments": segments }),
+        )
+    }
+
+    /// Send `SegmentSkipped`.
+    pub fn send_segment_skipped(
+        &self,
+        guild_id: impl Into<GuildId>,
+        segment: &sponsorblock::Segment,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "SegmentSkipped",
+            serde_json::json!({ "segment": segment }),
+        )
+    }
+
+    /// Send `ChaptersLoaded`.
+    pub fn send_chapters_loaded(
+        &self,
+        guild_id: impl Into<GuildId>,
+        chapters: &[sponsorblock::Chapter],
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "ChaptersLoaded",
+            serde_json::json!({ "chapters": chapters }),
+        )
+    }
+
+    /// Send `ChapterStarted`.
+    pub fn send_chapter_started(
+        &self,
+        guild_id: impl Into<GuildId>,
+        chapter: &sponsorblock::Chapter,
+    ) -> bool {
+        self.send_event(
+            guild_id.into(),
+            "ChapterStarted",
+            serde_json::json!({ "chapter": chapter }),
+        )
+    }
+
+    /// Send an `event` message of any type, like the ones sent by plugins.
+    ///
+    /// `fields` must be an object, and is merged into the message.
+    pub fn send_event(
+        &self,
+        guild_id: impl Into<GuildId>,
+        event_type: &str,
+        fields: serde_json::Value,
+    ) -> bool {
+        let mut message = serde_json::json!({
+            "op": "event",
+            "type": event_type,
+            "guildId": guild_id.into().0.to_string(),
+        });
+
+        if let (Some(message), serde_json::Value::Object(fields)) =
+            (message.as_object_mut(), fields)
+        {
+            message.extend(fields);
+        }
+
+        self.send(message)
+    }
+
+    /// Return this result when loading tracks with this identifier.
+    ///
+    /// Unknown identifiers return an empty result.
+    pub fn set_load_result(&self, identifier: &str, result: track::Track) {
+        self.state
+            .load_results
+            .lock()
+            .unwrap()
+            .insert(identifier.to_string(), result);
+    }
+
+    /// Return this result when searching for this query with LavaSearch.
+    ///
+    /// Only the kinds of results that were requested are returned, and unknown queries return
+    /// no content.
+    pub fn set_search_result(&self, query: &str, result: search::SearchResult) {
+        self.state
+            .search_results
+            .lock()
+            .unwrap()
+            .insert(query.to_string(), result);
+    }
+
+    /// Report this route planner status, None meaning no route planner is configured, which is
+    /// the default.
+    ///
+    /// Freeing addresses removes them from the failing addresses of the stored status.
+    pub fn set_route_planner(&self, status: Option<http::RoutePlannerStatus>) {
+        *self.state.route_planner.lock().unwrap() = status;
+    }
+
+    /// The route planner status, as stored by the server.
+    pub fn route_planner(&self) -> Option<http::RoutePlannerStatus> {
+        self.state.route_planner.lock().unwrap().clone()
+    }
+
+    /// Return these lyrics for the track with this identifier, both when loading them for a
+    /// track and for the track playing on a player.
+    ///
+    /// Unknown tracks return no content.
+    pub fn set_lyrics(&self, identifier: &str, lyrics: lyrics::Lyrics) {
+        self.state
+            .lyrics
+            .lock()
+            .unwrap()
+            .insert(identifier.to_string(), lyrics);
+    }
+
+    /// Whether the player of this guild is subscribed to lyrics events.
+    pub fn is_subscribed_to_lyrics(&self, guild_id: impl Into<GuildId>) -> bool {
+        self.state
+            .lyrics_subscriptions
+            .lock()
+            .unwrap()
+            .contains(&guild_id.into())
+    }
+
+    /// The SponsorBlock categories of the player of this guild, as stored by the server.
+    pub fn sponsorblock_categories(
+        &self,
+        guild_id: impl Into<GuildId>,
+    ) -> Vec<sponsorblock::SegmentCategory> {
+        self.state
+            .sponsorblock
+            .lock()
+            .unwrap()
+            .get(&guild_id.into())
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    /// Return this response for every request to this path, instead of the default one.
+    ///
+    /// The path does not include the query, like "/v4/loadtracks". Calling this again for the
+    /// same method and path replaces the response.
+    pub fn respond(&self, method: Method, path: &str, response: MockResponse) {
+        let mut responses = self.state.responses.lock().unwrap();
+        responses.retain(|x| x.once || x.method != method || x.path != path);
+        responses.push(ScriptedResponse {
+            method,
+            path: path.to_string(),
+            response,
+            once: false,
+        });
+    }
+
+    /// Return this response for the next request to this path only.
+    ///
+    /// Responses queued for the same path are returned in order, before the ones set with
+    /// `respond`.
+    pub fn respond_once(&self, method: Method, path: &str, response: MockResponse) {
+        self.state.responses.lock().unwrap().push(ScriptedResponse {
+            method,
+            path: path.to_string(),
+            response,
+            once: true,
+        });
+    }
+
+    /// The player of this guild, as stored by the server.
+    pub fn player(&self, guild_id: impl Into<GuildId>) -> Option<player::Player> {
+        self.state
+            .players
+            .lock()
+            .unwrap()
+            .get(&guild_id.into())
+            .cloned()
+    }
+
+    /// Every player stored by the server.
+    pub fn players(&self) -> Vec<player::Player> {
+        self.state
+            .players
+            .lock()
+            .unwrap()
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    /// Every request received so far, in order.
+    pub fn requests(&self) -> Vec<ReceivedRequest> {
+        self.state.requests.lock().unwrap().clone()
+    }
+
+    /// Forget the requests received so far.
+    pub fn clear_requests(&self) {
+        self.state.requests.lock().unwrap().clear();
+    }
+
+    /// Wait for a request matching the filter, returning the first one, even if it was received
+    /// before calling this method.
+    pub async fn wait_for_request(
+        &self,
+        filter: impl Fn(&ReceivedRequest) -> bool,
+    ) -> ReceivedRequest {
+        let mut rx = self.state.request_count.subscribe();
+
+        loop {
+            if let Some(request) = self
+                .state
+                .requests
+                .lock()
+                .unwrap()
+                .iter()
+                .find(|x| filter(x))
+            {
+                return request.clone();
+            }
+
+            let _ = rx.changed().await;
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.accept.abort();
+
+        for task in self.state.tasks.lock().unwrap().drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl ReceivedRequest {
+    /// Get a parameter of the query, decoded.
+    pub fn query_param(&self, name: &str) -> Option<String> {
+        self.query.as_deref()?.split('&').find_map(|x| {
+            let (key, value) = x.split_once('=').unwrap_or((x, ""));
+
+            (key == name).then(|| {
+                urlencoding::decode(value)
+                    .map(|x| x.into_owned())
+                    .unwrap_or_else(|_| value.to_string())
+            })
+        })
+    }
+
+    /// Deserialize the JSON body of the request.
+    pub fn json<T: serde::de::DeserializeOwned>(&self) -> LavalinkResult<T> {
+        Ok(serde_json::from_slice(&self.body)?)
+    }
+}
+
+impl MockState {
+    fn send(&self, message: serde_json::Value) -> bool {
+        self.socket
+            .lock()
+            .unwrap()
+            .as_ref()
+            .is_some_and(|x| x.send(Message::Text(message.to_string())).is_ok())
+    }
+
+    fn scripted_response(&self, method: &Method, path: &str) -> Option<MockResponse> {
+        let mut responses = self.responses.lock().unwrap();
+        let matches = |x: &ScriptedResponse| x.method == method && x.path == path;
+
+        if let Some(idx) = responses.iter().position(|x| x.once && matches(x)) {
+            return Some(responses.remove(idx).response);
+        }
+
+        responses
+            .iter()
+            .find(|x| matches(x))
+            .map(|x| x.response.clone())
+    }
+}
+
+fn new_session_id() -> String {
+    use rand::Rng;
+
+    rand::thread_rng()
+        .sample_iter(&rand::distributions::Alphanumeric)
+        .take(16)
+        .map(char::from)
+        .collect()
+}
+
+fn now_millis() -> u64 {
+    std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|x| x.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+async fn handle_request(
+    state: Arc<MockState>,
+    mut request: Request<Incoming>,
+) -> Result<Response<Full<Bytes>>, Infallible> {
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().map(|x| x.to_string());
+    let headers = request.headers().clone();
+    let on_upgrade = hyper::upgrade::on(&mut request);
+
+    let body = match request.into_body().collect().await {
+        Ok(body) => body.to_bytes(),
+        Err(why) => {
+            return Ok(error_response(
+                StatusCode::BAD_REQUEST,
+                &why.to_string(),
+                &path,
+            ))
+        }
+    };
+
+    let received = ReceivedRequest {
+        method,
+        path,
+        query,
+        headers,
+        body,
+    };
+
+    state.requests.lock().unwrap().push(received.clone());
+    state.request_count.send_modify(|x| *x += 1);
+
+    if received
+        .headers
+        .get(header::AUTHORIZATION)
+        .map(|x| x.as_bytes())
+        != Some(state.password.as_bytes())
+    {
+        return Ok(error_response(
+            StatusCode::UNAUTHORIZED,
+            "Unauthorized",
+            &received.path,
+        ));
+    }
+
+    if received.path == "/v4/websocket" {
+        return Ok(accept_websocket(state, &received, on_upgrade));
+    }
+
+    if let Some(mut response) = state.scripted_response(&received.method, &received.path) {
+        while let MockResponse::Delayed(delay, inner) = response {
+            tokio::time::sleep(delay).await;
+            response = *inner;
+        }
+
+        return Ok(match response {
+            MockResponse::Json(status, body) => json_response(status_code(status), &body),
+            MockResponse::Text(status, body) => text_response(status_code(status), body),
+            MockResponse::Error(status, message) => {
+                error_response(status_code(status), &message, &received.path)
+            }
+            MockResponse::Delayed(..) => unreachable!(),
+        });
+    }
+
+    Ok(route(&state, &received))
+}
+
+fn accept_websocket(
+    state: Arc<MockState>,
+    request: &ReceivedRequest,
+    on_upgrade: hyper::upgrade::OnUpgrade,
+) -> Response<Full<Bytes>> {
+    let Some(key) = request.headers.get(header::SEC_WEBSOCKET_KEY) else {
+        return error_response(
+            StatusCode::BAD_REQUEST,
+            "Missing Sec-WebSocket-Key",
+            &request.path,
+        );
+    };
+
+    let previous_session = request
+        .headers
+        .get("Session-Id")
+        .and_then(|x| x.to_str().ok());
+
+    let (session_id, resumed) = {
+        let mut session_id = state.session_id.lock().unwrap();
+
+        if state.resuming.load(Ordering::SeqCst) && previous_session == Some(&*session_id) {
+            (session_id.clone(), true)
+        } else {
+            *session_id = new_session_id();
+            state.resuming.store(false, Ordering::SeqCst);
+            state.players.lock().unwrap().clear();
+            (session_id.clone(), false)
+        }
+    };
+
+    let connection = tokio::spawn({
+        let state = state.clone();
+
+        async move {
+            let upgraded = match on_upgrade.await {
+                Ok(upgraded) => upgraded,
+                Err(why) => {
+                    warn!(
+                        "Mock Lavalink server failed to upgrade a connection: {}",
+                        why
+                    );
+                    return;
+                }
+            };
+
+            let websocket =
+                WebSocketStream::from_raw_socket(TokioIo::new(upgraded), Role::Server, None).await;
+
+            serve_websocket(state, websocket, session_id, resumed).await;
+        }
+    });
+
+    state.tasks.lock().unwrap().push(connection.abort_handle());
+
+    Response::builder()
+        .status(StatusCode::SWITCHING_PROTOCOLS)
+        .header(header::CONNECTION, "Upgrade")
+        .header(header::UPGRADE, "websocket")
+        .header(
+            header::SEC_WEBSOCKET_ACCEPT,
+            derive_accept_key(key.as_bytes()),
+        )
+        .body(Full::default())
+        .unwrap()
+}
+
+async fn serve_websocket(
+    state: Arc<MockState>,
+    websocket: WebSocketStream<TokioIo<hyper::upgrade::Upgraded>>,
+    session_id: String,
+    resumed: bool,
+) {
+    let (tx, mut rx) = mpsc::unbounded_channel();
+    let (mut write, mut read) = websocket.split();
+
+    if let Some(previous) = state.socket.lock().unwrap().replace(tx.clone()) {
+        let _ = previous.send(Message::Close(None));
+    }
+
+    if state.auto_ready.load(Ordering::SeqCst) {
+        let _ = tx.send(Message::Text(
+            serde_json::json!({
+                "op": "ready",
+                "resumed": resumed,
+                "sessionId": session_id,
+            })
+            .to_string(),
+        ));
+    }
+
+    state.connection_count.send_modify(|x| *x += 1);
+
+    loop {
+        tokio::select! {
+            message = rx.recv() => match message {
+                Some(Message::Close(frame)) => {
+                    let _ = write.send(Message::Close(frame)).await;
+                    break;
+                }
+                Some(message) => {
+                    if write.send(message).await.is_err() {
+                        break;
+                    }
+                }
+                None => break,
+            },
+            message = read.next() => match message {
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => {}
+            },
+        }
+    }
+
+    let mut socket = state.socket.lock().unwrap();
+
+    if socket.as_ref().is_some_and(|x| x.same_channel(&tx)) {
+        *socket = None;
+    }
+}
+
+/// The default behaviour of every REST endpoint.
+fn route(state: &MockState, request: &ReceivedRequest) -> Response<Full<Bytes>> {
+    let segments = request.path.split('/').skip(1).collect::<Vec<_>>();
+
+    match (&request.method, segments.as_slice()) {
+        (&Method::GET, ["version"]) => text_response(StatusCode::OK, "4.0.0".to_string()),
+        (&Method::GET, ["v4", "info"]) => json_response(StatusCode::OK, &info()),
+        (&Method::GET, ["v4", "stats"]) => json_response(StatusCode::OK, &state.stats()),
+        (&Method::GET, ["v4", "loadtracks"]) => {
+            let identifier = request.query_param("identifier").unwrap_or_default();
+
+            let result = state
+                .load_results
+                .lock()
+                .unwrap()
+                .get(&identifier)
+                .cloned()
+                .unwrap_or(track::Track {
+                    load_type: track::TrackLoadType::Empty,
+                    data: None,
+                });
+
+            json_response(StatusCode::OK, &result)
+        }
+        (&Method::GET, ["v4", "decodetrack"]) => {
+            let encoded = request.query_param("encodedTrack").unwrap_or_default();
+
+            match track::TrackData::decode(&encoded) {
+                Ok(track) => json_response(StatusCode::OK, &track),
+                Err(why) => {
+                    error_response(StatusCode::BAD_REQUEST, &why.to_string(), &request.path)
+                }
+            }
+        }
+        (&Method::POST, ["v4", "decodetracks"]) => {
+            let tracks = request.json::<Vec<String>>().and_then(|x| {
+                x.iter()
+                    .map(|x| track::TrackData::decode(x))
+                    .collect::<LavalinkResult<Vec<_>>>()
+            });
+
+            match tracks {
+                Ok(tracks) => json_response(StatusCode::OK, &tracks),
+                Err(why) => {
+                    error_response(StatusCode::BAD_REQUEST, &why.to_string(), &request.path)
+                }
+            }
+        }
+        (&Method::GET, ["v4", "loadsearch"]) => {
+            let query = request.query_param("query").unwrap_or_default();
+            let types = request.query_param("types");
+            let wants = |kind: &str| match types.as_deref() {
+                Some(types) => types.split(',').any(|x| x == kind),
+                None => true,
+            };
+
+            let Some(mut result) = state.search_results.lock().unwrap().get(&query).cloned() else {
+                return text_response(StatusCode::NO_CONTENT, String::new());
+            };
+
+            if !wants("track") {
+                result.tracks.clear();
+            }
+            if !wants("album") {
+                result.albums.clear();
+            }
+            if !wants("artist") {
+                result.artists.clear();
+            }
+            if !wants("playlist") {
+                result.playlists.clear();
+            }
+            if !wants("text") {
+                result.texts.clear();
+            }
+
+            json_response(StatusCode::OK, &result)
+        }
+        (&Method::GET, ["v4", "lyrics"]) => {
+            let encoded = request.query_param("track").unwrap_or_default();
+
+            match track::TrackData::decode(&encoded) {
+                Ok(track) => state.lyrics_response(&track),
+                Err(why) => {
+                    error_response(StatusCode::BAD_REQUEST, &why.to_string(), &request.path)
+                }
+            }
+        }
+        (&Method::GET, ["v4", "routeplanner", "status"]) => {
+            match &*state.route_planner.lock().unwrap() {
+                Some(status) => json_response(StatusCode::OK, status),
+                None => text_response(StatusCode::NO_CONTENT, String::new()),
+            }
+        }
+        (&Method::POST, ["v4", "routeplanner", "free", kind @ ("address" | "all")]) => {
+            let address = if *kind == "address" {
+                match request.json::<serde_json::Value>() {
+                    Ok(body) => match body["address"].as_str() {
+                        Some(address) => Some(address.to_string()),
+                        None => {
+                            return error_response(
+                                StatusCode::BAD_REQUEST,
+                                "No address provided",
+                                &request.path,
+                            )
+                        }
+                    },
+                    Err(why) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            &why.to_string(),
+                            &request.path,
+                        )
+                    }
+                }
+            } else {
+                None
+            };
+
+            let mut route_planner = state.route_planner.lock().unwrap();
+
+            let Some(status) = route_planner.as_mut() else {
+                return error_response(
+                    StatusCode::INTERNAL_SERVER_ERROR,
+                    "Can't access disabled route planner",
+                    &request.path,
+                );
+            };
+
+            let failing_addresses = match status {
+                http::RoutePlannerStatus::RotatingIp(x) => &mut x.failing_addresses,
+                http::RoutePlannerStatus::NanoIp(x) => &mut x.failing_addresses,
+                http::RoutePlannerStatus::RotatingNanoIp(x) => &mut x.failing_addresses,
+                http::RoutePlannerStatus::BalancingIp(x) => &mut x.failing_addresses,
+            };
+
+            match address {
+                Some(address) => failing_addresses.retain(|x| x.failing_address != address),
+                None => failing_addresses.clear(),
+            }
+
+            text_response(StatusCode::NO_CONTENT, String::new())
+        }
+        (method, ["v4", "sessions", session_id, rest @ ..]) => {
+            if *session_id != *state.session_id.lock().unwrap() {
+                return error_response(StatusCode::NOT_FOUND, "Session not found", &request.path);
+            }
+
+            match (method, rest) {
+                (&Method::PATCH, []) => match request.json::<http::ResumingState>() {
+                    Ok(resuming_state) => {
+                        if let Some(resuming) = resuming_state.resuming {
+                            state.resuming.store(resuming, Ordering::SeqCst);
+                        }
+
+                        json_response(
+                            StatusCode::OK,
+                            &http::ResumingState {
+                                resuming: Some(state.resuming.load(Ordering::SeqCst)),
+                                timeout: resuming_state.timeout.or(Some(60)),
+                            },
+                        )
+                    }
+                    Err(why) => {
+                        error_response(StatusCode::BAD_REQUEST, &why.to_string(), &request.path)
+                    }
+                },
+                (&Method::GET, ["players"]) => json_response(
+                    StatusCode::OK,
+                    &state.players.lock().unwrap().values().collect::<Vec<_>>(),
+                ),
+                (
+                    method,
+                    ["players", guild_id, rest @ ("track" | "lyrics" | "sponsorblock"), target],
+                ) => {
+                    let Ok(guild_id) = guild_id.parse::<GuildId>() else {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Invalid guild ID",
+                            &request.path,
+                        );
+                    };
+
+                    match (method, *rest, *target) {
+                        (&Method::GET, "track", "lyrics") => {
+                            let track = match state.players.lock().unwrap().get(&guild_id) {
+                                Some(player) => player.track.clone(),
+                                None => {
+                                    return error_response(
+                                        StatusCode::NOT_FOUND,
+                                        "Player not found",
+                                        &request.path,
+                                    )
+                                }
+                            };
+
+                            match track {
+                                Some(track) => state.lyrics_response(&track),
+                                None => text_response(StatusCode::NO_CONTENT, String::new()),
+                            }
+                        }
+                        (&Method::POST, "lyrics", "subscribe") => {
+                            state.lyrics_subscriptions.lock().unwrap().insert(guild_id);
+                            text_response(StatusCode::NO_CONTENT, String::new())
+                        }
+                        (&Method::DELETE, "lyrics", "subscribe") => {
+                            state.lyrics_subscriptions.lock().unwrap().remove(&guild_id);
+                            text_response(StatusCode::NO_CONTENT, String::new())
+                        }
+                        (&Method::GET, "sponsorblock", "categories") => json_response(
+                            StatusCode::OK,
+                            &state
+                                .sponsorblock
+                                .lock()
+                                .unwrap()
+                                .get(&guild_id)
+                                .cloned()
+                                .unwrap_or_default(),
+                        ),
+                        (&Method::PUT, "sponsorblock", "categories") => {
+                            match request.json::<Vec<sponsorblock::SegmentCategory>>() {
+                                Ok(categories) => {
+                                    state
+                                        .sponsorblock
+                                        .lock()
+                                        .unwrap()
+                                        .insert(guild_id, categories);
+                                    text_response(StatusCode::NO_CONTENT, String::new())
+                                }
+                                Err(why) => error_response(
+                                    StatusCode::BAD_REQUEST,
+                                    &why.to_string(),
+                                    &request.path,
+                                ),
+                            }
+                        }
+                        (&Method::DELETE, "sponsorblock", "categories") => {
+                            state.sponsorblock.lock().unwrap().remove(&guild_id);
+                            text_response(StatusCode::NO_CONTENT, String::new())
+                        }
+                        _ => error_response(StatusCode::NOT_FOUND, "Not Found", &request.path),
+                    }
+                }
+                (method, ["players", guild_id]) => {
+                    let Ok(guild_id) = guild_id.parse::<GuildId>() else {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            "Invalid guild ID",
+                            &request.path,
+                        );
+                    };
+
+                    match *method {
+                        Method::GET => match state.players.lock().unwrap().get(&guild_id) {
+                            Some(player) => json_response(StatusCode::OK, player),
+                            None => error_response(
+                                StatusCode::NOT_FOUND,
+                                "Player not found",
+                                &request.path,
+                            ),
+                        },
+                        Method::PATCH => state.update_player(guild_id, request),
+                        Method::DELETE => {
+                            state.players.lock().unwrap().remove(&guild_id);
+                            text_response(StatusCode::NO_CONTENT, String::new())
+                        }
+                        _ => error_response(
+                            StatusCode::METHOD_NOT_ALLOWED,
+                            "Method Not Allowed",
+                            &request.path,
+                        ),
+                    }
+                }
+                _ => error_response(StatusCode::NOT_FOUND, "Not Found", &request.path),
+            }
+        }
+        _ => error_response(StatusCode::NOT_FOUND, "Not Found", &request.path),
+    }
+}
+
+impl MockState {
+    fn update_player(&self, guild_id: GuildId, request: &ReceivedRequest) -> Response<Full<Bytes>> {
+        let update = match request.json::<http::UpdatePlayer>() {
+            Ok(update) => update,
+            Err(why) => {
+                return error_response(StatusCode::BAD_REQUEST, &why.to_string(), &request.path)
+            }
+        };
+
+        let no_replace = request.query_param("noReplace").as_deref() == Some("true");
+
+        let mut players = self.players.lock().unwrap();
+
+        let player = players.entry(guild_id).or_insert_with(|| player::Player {
+            guild_id,
+            track: None,
+            volume: 100,
+            paused: false,
+            state: player::State {
+                time: now_millis(),
+                position: 0,
+                connected: true,
+                ping: Some(0),
+            },
+            filters: None,
+            voice: player::ConnectionInfo {
+                endpoint: String::new(),
+                token: String::new(),
+                session_id: String::new(),
+            },
+        });
+
+        if let Some(track) = update.track {
+            let new_track = match (track.encoded, track.identifier) {
+                (Some(encoded), _) => match track::TrackData::decode(&encoded) {
+                    Ok(new_track) => Some(new_track),
+                    Err(why) => {
+                        return error_response(
+                            StatusCode::BAD_REQUEST,
+                            &why.to_string(),
+                            &request.path,
+                        )
+                    }
+                },
+                (None, Some(identifier)) => {
+                    let result = self.load_results.lock().unwrap().get(&identifier).cloned();
+
+                    match result.and_then(|x| x.data) {
+                        Some(track::TrackLoadData::Track(new_track)) => Some(new_track),
+                        Some(track::TrackLoadData::Search(tracks)) if !tracks.is_empty() => {
+                            tracks.into_iter().next()
+                        }
+                        _ => {
+                            return error_response(
+                                StatusCode::BAD_REQUEST,
+                                "No track found for the identifier",
+                                &request.path,
+                            )
+                        }
+                    }
+                }
+                (None, None) => None,
+            };
+
+            if !(no_replace && player.track.is_some() && new_track.is_some()) {
+                player.track = new_track.map(|mut x| {
+                    x.user_data = track.user_data;
+                    x
+                });
+                player.state.position = 0;
+            }
+        }
+
+        if let Some(position) = update.position {
+            player.state.position = position;
+        }
+        if let Some(volume) = update.volume {
+            player.volume = volume;
+        }
+        if let Some(paused) = update.paused {
+            player.paused = paused;
+        }
+        if let Some(filters) = update.filters {
+            player.filters = Some(filters);
+        }
+        if let Some(voice) = update.voice {
+            player.voice = voice;
+        }
+
+        player.state.time = now_millis();
+
+        json_response(StatusCode::OK, player)
+    }
+
+    fn stats(&self) -> events::Stats {
+        let players = self.players.lock().unwrap();
+
+        events::Stats {
+            op: "stats".to_string(),
+            players: players.len() as u64,
+            playing_players: players
+                .values()
+                .filter(|x| x.track.is_some() && !x.paused)
+                .count() as u64,
+            uptime: 0,
+            memory: events::Memory {
+                free: 1 << 28,
+                used: 1 << 28,
+                allocated: 1 << 29,
+                reservable: 1 << 30,
+            },
+            cpu: events::Cpu {
+                cores: 1,
+                system_load: 0.0,
+                lavalink_load: 0.0,
+            },
+            frame_stats: None,
+        }
+    }
+
+    fn lyrics_response(&self, track: &track::TrackData) -> Response<Full<Bytes>> {
+        match self.lyrics.lock().unwrap().get(&track.info.identifier) {
+            Some(lyrics) => json_response(StatusCode::OK, lyrics),
+            None => text_response(StatusCode::NO_CONTENT, String::new()),
+        }
+    }
+}
+
+fn info() -> http::Info {
+    http::Info {
+        version: http::Version {
+            semver: "4.0.0".to_string(),
+            major: 4,
+            minor: 0,
+            patch: 0,
+            pre_release: None,
+            build: None,
+        },
+        build_time: 0,
+        git: http::Git {
+            branch: "mock".to_string(),
+            commit: "mock".to_string(),
+            commit_time: 0,
+        },
+        jvm: "mock".to_string(),
+        lavaplayer: "mock".to_string(),
+        source_managers: vec![],
+        filters: vec![
+            "volume".to_string(),
+            "equalizer".to_string(),
+            "karaoke".to_string(),
+            "timescale".to_string(),
+            "tremolo".to_string(),
+            "vibrato".to_string(),
+            "distortion".to_string(),
+            "rotation".to_string(),
+            "channelMix".to_string(),
+            "lowPass".to_string(),
+        ],
+        plugins: vec![],
+    }
+}
+
+fn status_code(status: u16) -> StatusCode {
+    StatusCode::from_u16(status).unwrap_or(StatusCode::INTERNAL_SERVER_ERROR)
+}
+
+fn json_response<T: serde::Serialize + ?Sized>(
+    status: StatusCode,
+    body: &T,
+) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Full::from(serde_json::to_vec(body).unwrap()))
+        .unwrap()
+}
+
+fn text_response(status: StatusCode, body: String) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header(header::CONTENT_TYPE, "text/plain")
+        .body(Full::from(body))
+        .unwrap()
+}
+
+fn error_response(status: StatusCode, message: &str, path: &str) -> Response<Full<Bytes>> {
+    json_response(
+        status,
+        &ResponseError {
+            status: status.as_u16(),
+            timestamp: now_millis(),
+            error: status.canonical_reason().unwrap_or_default().to_string(),
+            message: message.to_string(),
+            path: path.to_string(),
+            trace: None,
+        },
+    )
+}
@@ -0,0 +1,90 @@
+#![allow(dead_code)]
+
+use lavalink_rs::client::EventStream;
+use lavalink_rs::model::{http, player, track};
+use lavalink_rs::prelude::*;
+use lavalink_rs::testing::{MockServer, ReceivedRequest};
+
+use std::time::Duration;
+
+use ::http::Method;
+use futures::StreamExt;
+
+pub const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub fn track(identifier: &str) -> track::TrackData {
+    track::TrackData::from_info(track::TrackInfo {
+        identifier: identifier.to_string(),
+        is_seekable: true,
+        author: "Author".to_string(),
+        length: 180000,
+        is_stream: false,
+        position: 0,
+        title: format!("Track {}", identifier),
+        uri: None,
+        artwork_url: None,
+        isrc: None,
+        source_name: "youtube".to_string(),
+    })
+    .unwrap()
+}
+
+pub fn connection_info() -> player::ConnectionInfo {
+    player::ConnectionInfo {
+        endpoint: "rotterdam1234.discord.media:443".to_string(),
+        token: "token".to_string(),
+        session_id: "session".to_string(),
+    }
+}
+
+/// Create a client connected to the server, waiting until it received `ready`.
+pub async fn connect(server: &MockServer, options: ClientOptions) -> LavalinkClient {
+    let client = LavalinkClient::new_with_options(
+        Default::default(),
+        vec![server.node_builder(UserId(1))],
+        NodeDistributionStrategy::new(),
+        options,
+        std::sync::Arc::new(()),
+    )
+    .await;
+
+    tokio::time::timeout(TIMEOUT, async {
+        let node = client.get_node_by_index(0).unwrap();
+
+        while **node.session_id.load() != server.session_id() {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("The client never received ready.");
+
+    client
+}
+
+pub fn is_playing(request: &ReceivedRequest, track: &track::TrackData) -> bool {
+    request.method == Method::PATCH
+        && request
+            .json::<http::UpdatePlayer>()
+            .ok()
+            .and_then(|x| x.track)
+            .and_then(|x| x.encoded)
+            .is_some_and(|x| x == track.encoded)
+}
+
+/// Wait for the next event the filter returns true for, skipping the others.
+pub async fn wait_for_event(
+    events: &mut EventStream,
+    filter: impl Fn(&LavalinkEvent) -> bool,
+) -> LavalinkEvent {
+    tokio::time::timeout(TIMEOUT, async {
+        loop {
+            let (_, event) = events.next().await.expect("The event stream ended.");
+
+            if filter(&event) {
+                return event;
+            }
+        }
+    })
+    .await
+    .expect("The event was never dispatched.")
+}
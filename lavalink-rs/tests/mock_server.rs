@@ -0,0 +1,401 @@
+mod common;
+
+use common::*;
+use lavalink_rs::error::LavalinkError;
+use lavalink_rs::model::search::{LavaSearchEngines, SearchResult, SearchText, SearchType};
+use lavalink_rs::model::{client::RequestPolicy, http, track};
+use lavalink_rs::prelude::*;
+use lavalink_rs::testing::{MockResponse, MockServer};
+
+use std::time::Duration;
+
+use ::http::Method;
+use futures::StreamExt;
+
+#[tokio::test]
+async fn serves_rest_endpoints() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let loaded = track::Track {
+        load_type: track::TrackLoadType::Track,
+        data: Some(track::TrackLoadData::Track(track("abc"))),
+    };
+    server.set_load_result("ytsearch:abc", loaded.clone());
+
+    assert_eq!(
+        client
+            .load_tracks(GuildId(1), "ytsearch:abc")
+            .await
+            .unwrap(),
+        loaded
+    );
+    assert_eq!(
+        client
+            .decode_track(GuildId(1), &track("abc").encoded)
+            .await
+            .unwrap(),
+        track("abc")
+    );
+    assert_eq!(
+        client.request_info(GuildId(1)).await.unwrap().version.major,
+        4
+    );
+
+    let request = server
+        .wait_for_request(|x| x.path == "/v4/loadtracks")
+        .await;
+    assert_eq!(
+        request.query_param("identifier").as_deref(),
+        Some("ytsearch:abc")
+    );
+    assert_eq!(request.headers["User-Id"], "1");
+}
+
+#[tokio::test]
+async fn returns_scripted_responses() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    server.respond_once(
+        Method::GET,
+        "/v4/info",
+        MockResponse::Error(500, "Something broke".to_string()),
+    );
+
+    match client.request_info(GuildId(1)).await {
+        Err(LavalinkError::ResponseError(why)) => {
+            assert_eq!(why.status, 500);
+            assert_eq!(why.message, "Something broke");
+        }
+        x => panic!("Expected a response error, got {:?}", x),
+    }
+
+    assert!(client.request_info(GuildId(1)).await.is_ok());
+}
+
+#[tokio::test]
+async fn retries_idempotent_requests() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let node = client.get_node_by_index(0).unwrap();
+
+    server.respond_once(
+        Method::GET,
+        "/v4/info",
+        MockResponse::Error(503, "Unavailable".to_string()),
+    );
+    server.respond_once(
+        Method::GET,
+        "/v4/info",
+        MockResponse::Error(502, "Bad gateway".to_string()),
+    );
+    server.clear_requests();
+
+    assert!(node.http.info().await.is_ok());
+    assert_eq!(
+        server
+            .requests()
+            .iter()
+            .filter(|x| x.path == "/v4/info")
+            .count(),
+        3
+    );
+
+    // Updating a player is not idempotent, so it's only sent once.
+    let path = format!("/v4/sessions/{}/players/1", server.session_id());
+    server.respond_once(
+        Method::PATCH,
+        &path,
+        MockResponse::Error(503, "Unavailable".to_string()),
+    );
+    server.clear_requests();
+
+    match node
+        .http
+        .update_player(
+            GuildId(1),
+            &server.session_id(),
+            &http::UpdatePlayer::default(),
+            false,
+        )
+        .await
+    {
+        Err(LavalinkError::ResponseError(why)) => assert_eq!(why.status, 503),
+        x => panic!("Expected a response error, got {:?}", x),
+    }
+    assert_eq!(server.requests().len(), 1);
+}
+
+#[tokio::test]
+async fn times_out_and_cancels_requests() {
+    let server = MockServer::start().await;
+    let client = connect(
+        &server,
+        ClientOptions {
+            request_policy: RequestPolicy {
+                timeout: Some(Duration::from_millis(200)),
+                max_retries: 0,
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await;
+    let node = client.get_node_by_index(0).unwrap();
+
+    server.respond(
+        Method::GET,
+        "/v4/info",
+        MockResponse::Delayed(
+            Duration::from_secs(2),
+            Box::new(MockResponse::Error(500, "Too late".to_string())),
+        ),
+    );
+
+    assert!(matches!(
+        node.http.info().await,
+        Err(LavalinkError::Timeout)
+    ));
+
+    let mut http = node.http.clone();
+    http.policy.timeout = None;
+
+    let request = tokio::spawn(async move { http.info().await });
+    tokio::time::sleep(Duration::from_millis(100)).await;
+    node.http.cancel_requests();
+
+    let result = tokio::time::timeout(TIMEOUT, request)
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(result, Err(LavalinkError::Cancelled)));
+}
+
+#[tokio::test]
+async fn loads_search_results() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let query = LavaSearchEngines::Spotify.to_query("Forget Not");
+    assert_eq!(query, "spsearch:Forget Not");
+
+    let album = track::PlaylistData {
+        info: track::PlaylistInfo {
+            name: "Urn".to_string(),
+            selected_track: None,
+        },
+        tracks: vec![track("a")],
+        plugin_info: None,
+    };
+
+    server.set_search_result(
+        &query,
+        SearchResult {
+            tracks: vec![track("a"), track("b")],
+            albums: vec![album.clone()],
+            texts: vec![SearchText {
+                text: "Forget Not Ne Obliviscaris".to_string(),
+                plugin: None,
+            }],
+            ..Default::default()
+        },
+    );
+
+    let result = client
+        .load_search(GuildId(1), &query, &[SearchType::Album, SearchType::Text])
+        .await
+        .unwrap();
+
+    assert!(result.tracks.is_empty());
+    assert_eq!(result.albums, [album]);
+    assert_eq!(result.texts[0].text, "Forget Not Ne Obliviscaris");
+
+    let result = client.load_search(GuildId(1), &query, &[]).await.unwrap();
+    assert_eq!(result.tracks.len(), 2);
+
+    // Nothing found responds with no content.
+    let result = client
+        .load_search(GuildId(1), "spsearch:nothing", &[])
+        .await
+        .unwrap();
+    assert!(result.is_empty());
+}
+
+#[tokio::test]
+async fn frees_route_planner_addresses() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    assert_eq!(
+        client
+            .request_route_planner_status(GuildId(1))
+            .await
+            .unwrap(),
+        None
+    );
+    assert!(client.free_all_addresses(GuildId(1)).await.is_err());
+
+    // The example of the Lavalink documentation.
+    let status = serde_json::from_value::<http::RoutePlannerStatus>(serde_json::json!({
+        "class": "RotatingNanoIpRoutePlanner",
+        "details": {
+            "ipBlock": {
+                "type": "Inet6Address",
+                "size": "1208925819614629174706176"
+            },
+            "failingAddresses": [
+                {
+                    "failingAddress": "/1.0.0.0",
+                    "failingTimestamp": 1573520707545u64,
+                    "failingTime": "Mon Nov 11 20:05:07 EST 2019"
+                },
+                {
+                    "failingAddress": "/1.0.0.1",
+                    "failingTimestamp": 1573520707545u64,
+                    "failingTime": "Mon Nov 11 20:05:07 EST 2019"
+                }
+            ],
+            "blockIndex": "0",
+            "currentAddressIndex": "36792023813"
+        }
+    }))
+    .unwrap();
+    server.set_route_planner(Some(status.clone()));
+
+    let received = client
+        .request_route_planner_status(GuildId(1))
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(received, status);
+    assert_eq!(received.ip_block().kind, http::IpBlockType::Inet6Address);
+
+    client.free_address(GuildId(1), "/1.0.0.0").await.unwrap();
+    assert_eq!(
+        server
+            .route_planner()
+            .unwrap()
+            .failing_addresses()
+            .iter()
+            .map(|x| x.failing_address.as_str())
+            .collect::<Vec<_>>(),
+        ["/1.0.0.1"]
+    );
+
+    client.free_all_addresses(GuildId(1)).await.unwrap();
+    assert!(server
+        .route_planner()
+        .unwrap()
+        .failing_addresses()
+        .is_empty());
+}
+
+#[tokio::test]
+async fn dispatches_pushed_events() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let mut events = client.subscribe().guild(GuildId(1));
+
+    assert!(server.send_track_start(GuildId(1), &track("abc")));
+    assert!(server.send_track_stuck(GuildId(1), &track("abc"), 10000));
+
+    let (_, event) = tokio::time::timeout(TIMEOUT, events.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(event, LavalinkEvent::TrackStart(x) if x.track == track("abc")));
+
+    let (_, event) = tokio::time::timeout(TIMEOUT, events.next())
+        .await
+        .unwrap()
+        .unwrap();
+    assert!(matches!(event, LavalinkEvent::TrackStuck(x) if x.threshold_ms == 10000));
+}
+
+#[tokio::test]
+async fn resumes_the_session_after_reconnecting() {
+    let server = MockServer::start().await;
+    let client = connect(
+        &server,
+        ClientOptions {
+            resume_timeout: Some(Duration::from_secs(60)),
+            reconnect_policy: lavalink_rs::model::client::ReconnectPolicy {
+                initial_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await;
+
+    let session_id = server.session_id();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| {
+            x.method == Method::PATCH && x.path == format!("/v4/sessions/{}", session_id)
+        }),
+    )
+    .await
+    .unwrap();
+
+    client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    server.clear_requests();
+    server.disconnect();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| {
+            x.method == Method::GET && x.path == format!("/v4/sessions/{}/players", session_id)
+        }),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(server.connection_count(), 2);
+    assert_eq!(server.session_id(), session_id);
+    assert!(server.player(GuildId(1)).is_some());
+
+    let handshake = server
+        .requests()
+        .into_iter()
+        .find(|x| x.path == "/v4/websocket")
+        .unwrap();
+    assert_eq!(handshake.headers["Session-Id"], session_id.as_str());
+}
+
+#[tokio::test]
+async fn dispatches_node_connection_events() {
+    let server = MockServer::start().await;
+    let client = connect(
+        &server,
+        ClientOptions {
+            reconnect_policy: lavalink_rs::model::client::ReconnectPolicy {
+                initial_delay: Duration::from_millis(10),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+    )
+    .await;
+    let mut events = client.subscribe().node(0);
+
+    server.disconnect();
+
+    let event = wait_for_event(&mut events, |x| {
+        matches!(x, LavalinkEvent::NodeDisconnected(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::NodeDisconnected(x) if !x.removed));
+
+    let event = wait_for_event(&mut events, |x| {
+        matches!(x, LavalinkEvent::NodeConnected(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::NodeConnected(x) if x.node_id == 0));
+}
@@ -0,0 +1,120 @@
+mod common;
+
+use common::*;
+use lavalink_rs::model::events;
+use lavalink_rs::prelude::*;
+use lavalink_rs::testing::MockServer;
+
+use std::time::Duration;
+
+/// Create a client connected to every server, tagging each node with its regions.
+async fn connect_all(
+    servers: &[(&MockServer, &str)],
+    strategy: NodeDistributionStrategy,
+) -> LavalinkClient {
+    let client = LavalinkClient::new(
+        Default::default(),
+        servers
+            .iter()
+            .map(|(server, region)| NodeBuilder {
+                regions: vec![region.to_string()],
+                ..server.node_builder(UserId(1))
+            })
+            .collect(),
+        strategy,
+    )
+    .await;
+
+    tokio::time::timeout(TIMEOUT, async {
+        for (idx, (server, _)) in servers.iter().enumerate() {
+            let node = client.get_node_by_index(idx).unwrap();
+
+            while **node.session_id.load() != server.session_id() {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+            }
+        }
+    })
+    .await
+    .expect("The client never received ready.");
+
+    client
+}
+
+#[tokio::test]
+async fn selects_the_node_with_the_lowest_penalty() {
+    let busy = MockServer::start().await;
+    let idle = MockServer::start().await;
+    let client = connect_all(
+        &[(&busy, "europe"), (&idle, "europe")],
+        NodeDistributionStrategy::lowest_penalty(),
+    )
+    .await;
+
+    let mut stats = serde_json::from_value::<events::Stats>(serde_json::json!({
+        "players": 40,
+        "playingPlayers": 40,
+        "uptime": 1000,
+        "memory": {"free": 100, "used": 100, "allocated": 200, "reservable": 1000},
+        "cpu": {"cores": 4, "systemLoad": 0.6, "lavalinkLoad": 0.4},
+        "frameStats": null,
+    }))
+    .unwrap();
+
+    assert!(busy.send_stats(&stats));
+
+    stats.players = 0;
+    stats.playing_players = 0;
+    stats.cpu.system_load = 0.05;
+    assert!(idle.send_stats(&stats));
+
+    tokio::time::timeout(TIMEOUT, async {
+        while (0..2).any(|x| client.get_node_by_index(x).unwrap().stats.load().is_none()) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .unwrap();
+
+    assert_eq!(client.get_node_for_guild(GuildId(1)).await.id, 1);
+}
+
+#[tokio::test]
+async fn moves_players_when_the_voice_region_changes() {
+    let europe = MockServer::start().await;
+    let us_east = MockServer::start().await;
+    let client = connect_all(
+        &[(&europe, "europe"), (&us_east, "us-east")],
+        NodeDistributionStrategy::by_region(),
+    )
+    .await;
+    let mut subscriber = client.subscribe().guild(GuildId(1));
+
+    // The endpoint of the connection info is in Rotterdam.
+    client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    assert_eq!(client.get_node_for_guild(GuildId(1)).await.id, 0);
+    assert!(europe.player(GuildId(1)).is_some());
+
+    client.handle_voice_server_update(
+        GuildId(1),
+        "new token".to_string(),
+        Some("newark1234.discord.media:443".to_string()),
+    );
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::PlayerMigrated(_))
+    })
+    .await;
+    assert!(matches!(
+        event,
+        LavalinkEvent::PlayerMigrated(x)
+            if x.new_node_id == 1 && x.reason == events::MigrationReason::RegionChanged
+    ));
+    let voice = us_east.player(GuildId(1)).unwrap().voice;
+    assert_eq!(voice.endpoint, "newark1234.discord.media:443");
+    assert_eq!(voice.token, "new token");
+    assert_eq!(voice.session_id, connection_info().session_id);
+    assert!(europe.player(GuildId(1)).is_none());
+}
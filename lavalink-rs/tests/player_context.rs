@@ -0,0 +1,866 @@
+mod common;
+
+use common::*;
+use lavalink_rs::error::LavalinkError;
+use lavalink_rs::model::sponsorblock::{self, SegmentCategory};
+use lavalink_rs::model::{events, http, lyrics};
+use lavalink_rs::player_context::{QueueChange, QueueUpdate};
+use lavalink_rs::prelude::*;
+use lavalink_rs::testing::{MockServer, ReceivedRequest};
+
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::StreamExt;
+
+#[tokio::test]
+async fn player_context_plays_the_queue() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    assert_eq!(server.player(GuildId(1)).unwrap().voice, connection_info());
+
+    player
+        .get_queue()
+        .append(VecDeque::from([
+            TrackInQueue::from(track("a")),
+            TrackInQueue::from(track("b")),
+        ]))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        server.player(GuildId(1)).unwrap().track.map(|x| x.info),
+        Some(track("a").info)
+    );
+
+    server.send_track_end(GuildId(1), &track("a"), events::TrackEndReason::Finished);
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("b"))),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn repeats_the_current_track() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    queue.set_repeat_mode(RepeatMode::Track).await.unwrap();
+    assert_eq!(queue.get_repeat_mode().await.unwrap(), RepeatMode::Track);
+
+    queue
+        .append(VecDeque::from([
+            TrackInQueue::from(track("a")),
+            TrackInQueue::from(track("b")),
+        ]))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    server.clear_requests();
+    server.send_track_end(GuildId(1), &track("a"), events::TrackEndReason::Finished);
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+    assert_eq!(queue.get_count().await.unwrap(), 1);
+
+    // Failed tracks are not retried forever.
+    server.clear_requests();
+    server.send_track_end(GuildId(1), &track("a"), events::TrackEndReason::LoadFailed);
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("b"))),
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn repeats_the_queue() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    queue.set_repeat_mode(RepeatMode::Queue).await.unwrap();
+    assert_eq!(queue.get_repeat_mode().await.unwrap(), RepeatMode::Queue);
+    queue
+        .append(VecDeque::from([
+            TrackInQueue::from(track("a")),
+            TrackInQueue::from(track("b")),
+        ]))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    for (ended, next) in [("a", "b"), ("b", "a"), ("a", "b")] {
+        server.clear_requests();
+        server.send_track_end(GuildId(1), &track(ended), events::TrackEndReason::Finished);
+
+        tokio::time::timeout(
+            TIMEOUT,
+            server.wait_for_request(|x| is_playing(x, &track(next))),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(
+            queue.get_queue().await.unwrap(),
+            VecDeque::from([TrackInQueue::from(track(ended))])
+        );
+    }
+}
+
+#[tokio::test]
+async fn shuffles_the_queue() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    // Keep the queue from starting to play.
+    player.finish(false).unwrap();
+
+    queue
+        .append((0..20).map(|x| track(&x.to_string()).into()).collect())
+        .await
+        .unwrap();
+
+    let identifiers = |tracks: VecDeque<TrackInQueue>| {
+        let mut identifiers = tracks
+            .into_iter()
+            .map(|x| x.track.info.identifier)
+            .collect::<Vec<_>>();
+        identifiers.sort();
+        identifiers
+    };
+
+    let before = queue.get_queue().await.unwrap();
+    queue.shuffle().await.unwrap();
+    let after = queue.get_queue().await.unwrap();
+
+    assert_eq!(before.len(), 20);
+    assert_eq!(identifiers(before), identifiers(after));
+}
+
+#[tokio::test]
+async fn goes_back_to_the_previous_track() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    assert_eq!(player.previous().await.unwrap(), None);
+
+    queue
+        .append(VecDeque::from([
+            TrackInQueue::from(track("a")),
+            TrackInQueue::from(track("b")),
+            TrackInQueue::from(track("c")),
+        ]))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    server.send_track_end(GuildId(1), &track("a"), events::TrackEndReason::Finished);
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("b"))),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        queue.history().await.unwrap(),
+        VecDeque::from([TrackInQueue::from(track("a"))])
+    );
+
+    server.clear_requests();
+    assert_eq!(
+        player.previous().await.unwrap(),
+        Some(TrackInQueue::from(track("a")))
+    );
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(
+        queue.get_queue().await.unwrap(),
+        VecDeque::from([
+            TrackInQueue::from(track("b")),
+            TrackInQueue::from(track("c")),
+        ])
+    );
+    assert!(queue.history().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn validates_queue_mutations() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    // Keep the queue from starting to play.
+    player.finish(false).unwrap();
+
+    let identifiers = |tracks: VecDeque<TrackInQueue>| {
+        tracks
+            .into_iter()
+            .map(|x| x.track.info.identifier)
+            .collect::<Vec<_>>()
+    };
+
+    queue
+        .append(VecDeque::from(
+            ["a", "b", "c"].map(|x| TrackInQueue::from(track(x))),
+        ))
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        queue.remove(3).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 3, len: 3 })
+    ));
+    assert!(matches!(
+        queue.insert(4, track("d")).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 4, len: 3 })
+    ));
+    assert!(matches!(
+        queue.swap(3, track("d")).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 3, len: 3 })
+    ));
+    assert!(matches!(
+        queue.swap_indices(0, 3).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 3, len: 3 })
+    ));
+    assert!(matches!(
+        queue.move_track(0, 3).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 3, len: 3 })
+    ));
+    assert!(matches!(
+        queue.remove_range(1..4).await,
+        Err(LavalinkError::IndexOutOfBounds { index: 3, len: 3 })
+    ));
+    assert_eq!(
+        identifiers(queue.get_queue().await.unwrap()),
+        ["a", "b", "c"]
+    );
+
+    queue.insert(3, track("d")).await.unwrap();
+    assert_eq!(
+        queue.swap(0, track("e")).await.unwrap(),
+        TrackInQueue::from(track("a"))
+    );
+    queue.move_track(3, 0).await.unwrap();
+    assert_eq!(
+        identifiers(queue.get_queue().await.unwrap()),
+        ["d", "e", "b", "c"]
+    );
+
+    queue.swap_indices(0, 3).await.unwrap();
+    queue.swap_indices(3, 0).await.unwrap();
+    assert_eq!(
+        identifiers(queue.get_queue().await.unwrap()),
+        ["d", "e", "b", "c"]
+    );
+    queue.swap_indices(1, 2).await.unwrap();
+    queue.swap_indices(1, 2).await.unwrap();
+
+    assert_eq!(
+        queue.remove(1).await.unwrap(),
+        TrackInQueue::from(track("e"))
+    );
+    assert_eq!(
+        identifiers(queue.remove_range(1..).await.unwrap()),
+        ["b", "c"]
+    );
+    assert_eq!(identifiers(queue.get_queue().await.unwrap()), ["d"]);
+}
+
+#[tokio::test]
+async fn filters_the_queue() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    // Keep the queue from starting to play.
+    player.finish(false).unwrap();
+
+    let requested_by = |identifier: &str, requester_id: u64| {
+        let mut track = track(identifier);
+        track.user_data = Some(serde_json::json!({ "requester_id": requester_id }));
+        TrackInQueue::from(track)
+    };
+
+    queue
+        .append(VecDeque::from([
+            requested_by("a", 1),
+            requested_by("b", 2),
+            requested_by("a", 2),
+            requested_by("c", 1),
+            requested_by("b", 1),
+        ]))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        queue.dedupe_by_identifier().await.unwrap(),
+        VecDeque::from([requested_by("a", 2), requested_by("b", 1)])
+    );
+    assert_eq!(
+        queue
+            .drain_by_user_data(serde_json::json!({ "requester_id": 1 }))
+            .await
+            .unwrap(),
+        VecDeque::from([requested_by("a", 1), requested_by("c", 1)])
+    );
+    assert_eq!(
+        queue
+            .retain(|x| x.track.info.identifier != "b")
+            .await
+            .unwrap(),
+        VecDeque::from([requested_by("b", 2)])
+    );
+    assert!(queue.get_queue().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn streams_queue_changes() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    let queue = player.get_queue();
+
+    // Keep the queue from starting to play.
+    player.finish(false).unwrap();
+
+    queue.push_to_back(track("a")).await.unwrap();
+
+    let mut changes = queue.changes();
+
+    assert_eq!(
+        tokio::time::timeout(TIMEOUT, changes.next())
+            .await
+            .unwrap()
+            .unwrap(),
+        QueueUpdate {
+            revision: 1,
+            change: QueueChange::Reset {
+                tracks: VecDeque::from([TrackInQueue::from(track("a"))]),
+            },
+        }
+    );
+
+    queue
+        .append(VecDeque::from([
+            TrackInQueue::from(track("b")),
+            TrackInQueue::from(track("c")),
+        ]))
+        .await
+        .unwrap();
+    queue.move_track(2, 0).await.unwrap();
+    queue
+        .retain(|x| x.track.info.identifier == "c")
+        .await
+        .unwrap();
+    queue.clear().await.unwrap();
+
+    let updates = tokio::time::timeout(TIMEOUT, changes.by_ref().take(4).collect::<Vec<_>>())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        updates,
+        [
+            QueueUpdate {
+                revision: 2,
+                change: QueueChange::Added {
+                    index: 1,
+                    tracks: vec![track("b").into(), track("c").into()],
+                },
+            },
+            QueueUpdate {
+                revision: 3,
+                change: QueueChange::Moved { from: 2, to: 0 },
+            },
+            QueueUpdate {
+                revision: 4,
+                change: QueueChange::Removed {
+                    index: 1,
+                    tracks: vec![track("a").into(), track("b").into()],
+                },
+            },
+            QueueUpdate {
+                revision: 5,
+                change: QueueChange::Cleared,
+            },
+        ]
+    );
+    assert_eq!(queue.snapshot().await.unwrap().revision, 5);
+
+    // Changes that leave the queue as it was are not broadcast.
+    queue.append(VecDeque::new()).await.unwrap();
+    queue.clear().await.unwrap();
+    assert_eq!(queue.snapshot().await.unwrap().revision, 5);
+}
+
+#[tokio::test]
+async fn dispatches_player_lifecycle_events() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let mut subscriber = client.subscribe().guild(GuildId(1));
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::PlayerCreated(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::PlayerCreated(x) if x.node_id == 0));
+
+    player.queue(track("a")).await.unwrap();
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::TrackQueued(_))
+    })
+    .await;
+    assert!(
+        matches!(event, LavalinkEvent::TrackQueued(x) if x.index == 0 && x.tracks == [track("a")])
+    );
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    server.send_track_end(GuildId(1), &track("a"), events::TrackEndReason::Finished);
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::QueueEnded(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::QueueEnded(x) if x.track == track("a")));
+
+    player.play_now(&track("b")).await.unwrap();
+    server.send_track_end(GuildId(1), &track("b"), events::TrackEndReason::Finished);
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::QueueEnded(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::QueueEnded(x) if x.track == track("b")));
+
+    // Keep the queue from starting to play.
+    player.finish(false).unwrap();
+
+    for id in ["c", "d", "e"] {
+        player.queue(track(id)).await.unwrap();
+    }
+
+    for id in ["c", "d", "e"] {
+        let event = wait_for_event(&mut subscriber, |x| {
+            matches!(x, LavalinkEvent::TrackQueued(_))
+        })
+        .await;
+        assert!(matches!(event, LavalinkEvent::TrackQueued(x) if x.tracks == [track(id)]));
+    }
+
+    client.delete_player(GuildId(1)).await.unwrap();
+
+    let event = wait_for_event(&mut subscriber, |x| {
+        matches!(x, LavalinkEvent::PlayerDestroyed(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::PlayerDestroyed(x) if x.node_id == 0));
+}
+
+fn volume_filter(request: &ReceivedRequest) -> Option<f64> {
+    request
+        .json::<http::UpdatePlayer>()
+        .ok()
+        .and_then(|x| x.filters)
+        .and_then(|x| x.volume)
+}
+
+#[tokio::test]
+async fn fades_tracks_in_and_out() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    player
+        .queue(TrackInQueue {
+            fade_in: Some(Duration::from_millis(500)),
+            fade_out: Some(Duration::from_millis(500)),
+            ..track("a").into()
+        })
+        .await
+        .unwrap();
+
+    let start = tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+    assert_eq!(volume_filter(&start), Some(0.0));
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| volume_filter(x) == Some(1.0)),
+    )
+    .await
+    .unwrap();
+
+    server.clear_requests();
+
+    let mut state = server.player(GuildId(1)).unwrap().state;
+    state.position = track("a").info.length - 400;
+    server.send_player_update(GuildId(1), state);
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| volume_filter(x) == Some(0.0)),
+    )
+    .await
+    .unwrap();
+
+    // The fade out only ever lowers the volume.
+    let volumes = server
+        .requests()
+        .iter()
+        .filter_map(volume_filter)
+        .collect::<Vec<_>>();
+    assert!(volumes.windows(2).all(|x| x[0] >= x[1]));
+}
+
+#[tokio::test]
+async fn fades_the_volume() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    tokio::time::timeout(TIMEOUT, player.fade_to(50, Duration::from_millis(500)))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let volumes = server
+        .requests()
+        .iter()
+        .filter_map(volume_filter)
+        .collect::<Vec<_>>();
+    assert!(volumes.len() > 1);
+    assert!(volumes.windows(2).all(|x| x[0] > x[1]));
+    assert_eq!(
+        server.player(GuildId(1)).unwrap().filters.unwrap().volume,
+        Some(0.5)
+    );
+}
+
+#[tokio::test]
+async fn skips_with_a_fade() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    player
+        .get_queue()
+        .append(VecDeque::from([
+            TrackInQueue::from(track("a")),
+            TrackInQueue::from(track("b")),
+        ]))
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    tokio::time::timeout(TIMEOUT, player.skip_with_fade(Duration::from_millis(300)))
+        .await
+        .unwrap()
+        .unwrap();
+
+    let next = tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("b"))),
+    )
+    .await
+    .unwrap();
+
+    assert!(server
+        .requests()
+        .iter()
+        .any(|x| volume_filter(x) == Some(0.0)));
+
+    // The next track plays at the volume from before the fade.
+    let filters = next.json::<http::UpdatePlayer>().unwrap().filters.unwrap();
+    assert_eq!(filters.volume, None);
+}
+
+#[tokio::test]
+async fn loads_lyrics_and_dispatches_lyrics_events() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let mut events = client.subscribe().guild(GuildId(1));
+
+    let line = lyrics::LyricsLine {
+        timestamp: 1000,
+        duration: Some(2000),
+        line: "Forget not".to_string(),
+        plugin: None,
+    };
+    let found = lyrics::Lyrics {
+        source_name: "youtube".to_string(),
+        provider: "Musixmatch".to_string(),
+        text: None,
+        lines: vec![line.clone()],
+        plugin: None,
+    };
+    server.set_lyrics("a", found.clone());
+
+    assert_eq!(
+        client
+            .load_lyrics(GuildId(1), &track("a").encoded)
+            .await
+            .unwrap(),
+        Some(found.clone())
+    );
+    assert_eq!(
+        client
+            .load_lyrics(GuildId(1), &track("b").encoded)
+            .await
+            .unwrap(),
+        None
+    );
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+    assert_eq!(player.get_lyrics().await.unwrap(), None);
+
+    player.play(&track("a")).await.unwrap();
+    assert_eq!(player.get_lyrics().await.unwrap(), Some(found.clone()));
+
+    player.subscribe_lyrics().await.unwrap();
+    assert!(server.is_subscribed_to_lyrics(GuildId(1)));
+
+    server.send_lyrics_found(GuildId(1), &found);
+    server.send_lyrics_line(GuildId(1), 0, &line, false);
+
+    let event = wait_for_event(&mut events, |x| matches!(x, LavalinkEvent::LyricsFound(_))).await;
+    assert!(matches!(event, LavalinkEvent::LyricsFound(x) if x.lyrics == found));
+
+    let event = wait_for_event(&mut events, |x| matches!(x, LavalinkEvent::LyricsLine(_))).await;
+    assert!(matches!(event, LavalinkEvent::LyricsLine(x) if x.line_index == 0 && x.line == line));
+
+    player.unsubscribe_lyrics().await.unwrap();
+    assert!(!server.is_subscribed_to_lyrics(GuildId(1)));
+}
+
+#[tokio::test]
+async fn configures_sponsorblock_and_dispatches_its_events() {
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let mut events = client.subscribe().guild(GuildId(1));
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    assert!(player
+        .get_sponsorblock_categories()
+        .await
+        .unwrap()
+        .is_empty());
+
+    let categories = [SegmentCategory::Sponsor, SegmentCategory::Intro];
+    player
+        .set_sponsorblock_categories(&categories)
+        .await
+        .unwrap();
+    assert_eq!(server.sponsorblock_categories(GuildId(1)), categories);
+    assert_eq!(
+        player.get_sponsorblock_categories().await.unwrap(),
+        categories
+    );
+
+    let segment = sponsorblock::Segment {
+        category: SegmentCategory::Intro,
+        start: 0,
+        end: 12000,
+    };
+    server.send_segments_loaded(GuildId(1), std::slice::from_ref(&segment));
+    server.send_segment_skipped(GuildId(1), &segment);
+
+    let event = wait_for_event(&mut events, |x| {
+        matches!(x, LavalinkEvent::SegmentsLoaded(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::SegmentsLoaded(x) if x.segments == [segment.clone()]));
+
+    let event = wait_for_event(&mut events, |x| {
+        matches!(x, LavalinkEvent::SegmentSkipped(_))
+    })
+    .await;
+    assert!(matches!(event, LavalinkEvent::SegmentSkipped(x) if x.segment == segment));
+
+    player.clear_sponsorblock_categories().await.unwrap();
+    assert!(server.sponsorblock_categories(GuildId(1)).is_empty());
+}
+
+#[tokio::test]
+async fn round_trips_typed_user_data() {
+    #[derive(Debug, PartialEq, serde::Serialize, serde::Deserialize)]
+    struct Requester {
+        requester_id: u64,
+    }
+
+    let server = MockServer::start().await;
+    let client = connect(&server, Default::default()).await;
+    let mut events = client.subscribe().guild(GuildId(1));
+
+    let player = client
+        .create_player_context(GuildId(1), connection_info())
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        track("a").user_data_as::<Requester>(),
+        Err(LavalinkError::MissingUserData)
+    ));
+
+    player
+        .queue(
+            TrackInQueue::from(track("a"))
+                .with_user_data(&Requester { requester_id: 42 })
+                .unwrap(),
+        )
+        .await
+        .unwrap();
+
+    tokio::time::timeout(
+        TIMEOUT,
+        server.wait_for_request(|x| is_playing(x, &track("a"))),
+    )
+    .await
+    .unwrap();
+
+    let playing = player.get_player().await.unwrap().track.unwrap();
+    assert_eq!(
+        playing.user_data_as::<Requester>().unwrap(),
+        Requester { requester_id: 42 }
+    );
+    assert!(playing.user_data_as::<String>().is_err());
+
+    server.send_track_start(
+        GuildId(1),
+        &server.player(GuildId(1)).unwrap().track.unwrap(),
+    );
+
+    let event = wait_for_event(&mut events, |x| matches!(x, LavalinkEvent::TrackStart(_))).await;
+    let LavalinkEvent::TrackStart(event) = event else {
+        unreachable!()
+    };
+    assert_eq!(
+        event.track.user_data_as::<Requester>().unwrap(),
+        Requester { requester_id: 42 }
+    );
+}